@@ -0,0 +1,56 @@
+use std::{env, fs, io, path::PathBuf};
+
+use serde_json::json;
+
+#[path = "src/utils/atlas_packer.rs"]
+mod atlas_packer;
+
+use atlas_packer::{nine_slice_from_name, pack_images, AtlasConfig};
+
+/// Bakes every image in `assets/` into `atlas.png` and an `atlas.json`
+/// manifest describing where each one was placed. Both are written to
+/// OUT_DIR and embedded together by `load_texture_atlas`. Assets that
+/// don't fit on one page go on further pages stacked below it. Assets named
+/// like `button.9.4.png` are nine-sliced, see [nine_slice_from_name].
+fn main() {
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-changed=src/utils/atlas_packer.rs");
+
+    let mut asset_paths = fs::read_dir("assets").unwrap()
+        .map(|res| res.map(|e| e.path()))
+        .collect::<Result<Vec<_>, io::Error>>().unwrap();
+    // read_dir order is platform dependent, sorting keeps the atlas layout stable
+    asset_paths.sort();
+
+    let mut images = Vec::new();
+    let mut nine_slices = Vec::new();
+    for asset in asset_paths {
+        let (name, nine_slice) = nine_slice_from_name(asset.file_stem().unwrap().to_str().unwrap());
+        images.push((name.to_string(), image::open(asset.as_path()).unwrap().to_rgba8()));
+        nine_slices.push(nine_slice);
+    }
+
+    let config = AtlasConfig::default();
+    let (atlas, pages, rects) = pack_images(&images, config)
+        .unwrap_or_else(|| panic!("An asset is larger than a {0}x{0} atlas page", config.max_size));
+
+    let entries: Vec<_> = images.iter().zip(&rects).zip(&nine_slices).map(|(((name, _), rect), nine_slice)| json!({
+        "name": name,
+        "x_start": rect.x,
+        "y_start": rect.y,
+        "image_width": rect.width,
+        "image_height": rect.height,
+        "page": rect.page,
+        "nine_slice": nine_slice,
+    })).collect();
+    let manifest = json!({
+        "width": atlas.width(),
+        "height": atlas.height() / pages,
+        "pages": pages,
+        "entries": entries,
+    });
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    atlas.save(out_dir.join("atlas.png")).unwrap();
+    fs::write(out_dir.join("atlas.json"), serde_json::to_string_pretty(&manifest).unwrap()).unwrap();
+}
@@ -1,15 +1,19 @@
 use std::sync::{Arc, Mutex};
 
-use rendering::{definitions::UiAtlas, user_interface::{elements::{ElementType, InteractionResult}, interface::Interface}, RenderState};
-use winit::{application::ApplicationHandler, dpi::PhysicalSize, event_loop::{ControlFlow, EventLoop}, window::{CursorIcon, Window}};
+use rendering::{adapter::AdapterSelection, definitions::UiAtlas, render_config::RenderConfig, user_interface::{elements::{ElementType, InteractionResult}, interface::Interface}, RenderState};
+use winit::{application::ApplicationHandler, dpi::PhysicalSize, event_loop::{ControlFlow, EventLoop}, keyboard::ModifiersState, window::{CursorIcon, Window}};
 
-use crate::utils::{atlas_generation::generate_texture_atlas, components::header_componenet, definitions::{Edge, State}, state_events::{state_normal, state_resizing}};
+use crate::utils::{atlas_generation::load_texture_atlas, components::header_componenet, definitions::{Edge, State}, state_events::{state_normal, state_resizing}};
 
 mod utils;
 
+/// How much Ctrl+ and Ctrl- zoom the interface per press, and how far.
+const UI_ZOOM_STEP: f32 = 1.1;
+const UI_ZOOM_RANGE: (f32, f32) = (0.5, 3.0);
+
 fn main() {
-    let atlas = generate_texture_atlas();
-    App::new(atlas);
+    let atlas = load_texture_atlas();
+    App::run(atlas);
 }
 
 struct App {
@@ -18,26 +22,27 @@ struct App {
     interface: Arc<Mutex<Interface>>,
     window_size: PhysicalSize<u32>,
     cursor_position: [f32; 2],
+    modifiers: ModifiersState,
     selected_element: Option<(u32, ElementType)>,
     hovered: Option<u32>,
     last_hovered: u32,
-    atlas: UiAtlas,
     resizing: bool,
     state: State,
 }
 
 impl App {
-    fn new(atlas: UiAtlas) {
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn run(atlas: UiAtlas) {
         let mut app = Self {
             render_state: None,
             window_ref: None,
-            interface: Arc::new(Mutex::new(Interface::new(atlas.clone()))),
+            interface: Arc::new(Mutex::new(Interface::new(atlas))),
             window_size: PhysicalSize::new(0, 0),
             cursor_position: [0.0, 0.0],
+            modifiers: ModifiersState::empty(),
             selected_element: None,
             hovered: None,
             last_hovered: 0,
-            atlas,
             resizing: false,
             state: State::Normal,
         };
@@ -51,60 +56,16 @@ impl App {
     fn handle_click(&self, cursor_position: [f32; 2]) -> InteractionResult {
         let mut interface_guard = self.interface.lock().unwrap();
         let window_size = [self.window_size.width, self.window_size.height];
-        let mut result = InteractionResult::None;
-        let mut smallest_element = [0.5, 0.5, 1.0, 1.0];
 
-        for element in &mut interface_guard.elements {
-            let element_position = element.get_position(window_size);
-            let element_scale = element.get_scale(window_size);
-
-            if element.is_cursor_within_bounds(cursor_position, element_position, element_scale) {
-                if element.get_layer(smallest_element, window_size) {
-                    smallest_element = [element_position[0], element_position[1], element_scale[0], element_scale[1]];
-                    result = element.handle_click();
-                }
-            }
-        }
-        return result;
-    }
-
-    fn old_handle_hover(&self, cursor_position: [f32; 2]) -> Option<u32> {
-        let mut interface_guard = self.interface.lock().unwrap();
-        let window_size = [self.window_size.width, self.window_size.height];
-        let mut result = None;
-        let mut smallest_element = [0.5, 0.5, 1.0, 1.0];
-
-        for element in &mut interface_guard.elements {
-            let element_position = element.get_position(window_size);
-            let element_scale = element.get_scale(window_size);
-
-            if element.is_cursor_within_bounds(cursor_position, element_position, element_scale) {
-                if element.get_layer(smallest_element, window_size) {
-                    smallest_element = [element_position[0], element_position[1], element_scale[0], element_scale[1]];
-                    result = Some(element.get_id());
-                }
-            }
-        }
-        return result;
+        interface_guard.element_at(cursor_position, window_size)
+            .map_or(InteractionResult::None, |element| element.handle_click())
     }
 
     fn handle_hover(&mut self, cursor_position: [f32; 2]) -> Option<u32> {
-        let mut interface_guard = self.interface.lock().unwrap();
         let window_size = [self.window_size.width, self.window_size.height];
-        let mut result = None;
-        let mut smallest_element = [0.5, 0.5, 1.0, 1.0];
-
-        for element in &mut interface_guard.elements {
-            let element_position = element.get_position(window_size);
-            let element_scale = element.get_scale(window_size);
-
-            if element.is_cursor_within_bounds(cursor_position, element_position, element_scale) {
-                if element.get_layer(smallest_element, window_size) {
-                    smallest_element = [element_position[0], element_position[1], element_scale[0], element_scale[1]];
-                    result = Some(element.get_id());
-                }
-            }
-        }
+        let result = self.interface.lock().unwrap()
+            .element_at(cursor_position, window_size)
+            .map(|element| element.get_id());
 
         if result.is_some() {
             return result;
@@ -131,7 +92,7 @@ impl App {
             self.state = State::Normal
         }
 
-        return None;
+        None
     }
 
     fn handle_resizing(&self, cursor_position: [f32; 2], window_size: [f32; 2]) -> Edge {
@@ -155,7 +116,7 @@ impl App {
         };
 
         self.window_ref.clone().unwrap().set_cursor(cursor_icon);
-        return side;
+        side
     }
 
     fn highlight(&self, alpha: f32) -> bool {
@@ -169,19 +130,41 @@ impl App {
         false
     }
 
+    /// Multiplies the interface zoom by `factor`, or resets it when None.
+    fn zoom_interface(&mut self, factor: Option<f32>) {
+        {
+            let mut interface_guard = self.interface.lock().unwrap();
+            let zoom = factor.map_or(1.0, |factor| (interface_guard.zoom() * factor).clamp(UI_ZOOM_RANGE.0, UI_ZOOM_RANGE.1));
+            interface_guard.set_zoom(zoom);
+        }
+        self.rebuild_interface();
+    }
+
     fn rebuild_interface(&mut self) {
-        let new_interface_data = Self::build_project_view(self.atlas.clone());
+        // The current interface's atlas includes any textures added at runtime
+        let atlas = self.interface.lock().unwrap().atlas().clone();
+        let new_interface_data = Self::build_project_view(atlas);
 
-        if let Some(rs) = self.render_state.as_mut() {
-            let mut interface_guard = self.interface.lock().unwrap();
-            *interface_guard = new_interface_data;
+        let mut interface_guard = self.interface.lock().unwrap();
+        let previous = std::mem::replace(&mut *interface_guard, new_interface_data);
+        // Typed text, the zoom and GPU resources carry over, selection and hover follow their elements
+        let ids = interface_guard.reconcile(previous);
+        self.selected_element = self.selected_element.take().and_then(|(id, _)| {
+            let id = *ids.get(&id)?;
+            interface_guard.elements.iter().find(|element| element.get_id() == id).map(|element| (id, element.get_element_type()))
+        });
+        self.hovered = self.hovered.and_then(|id| ids.get(&id).copied());
+        if let Some(&id) = ids.get(&self.last_hovered) {
+            self.last_hovered = id;
+        }
 
-            interface_guard.initalize_text_brush(&rs.device, &rs.config, &rs.queue);
+        if let Some(rs) = self.render_state.as_ref() {
+            if !interface_guard.has_text_brush() {
+                interface_guard.initalize_text_brush(&rs.device, &rs.config, &rs.queue);
+            }
             interface_guard.initialize_interface_buffers(&rs.device, &rs.queue, [self.window_size.width, self.window_size.height]);
         } else {
             log::warn!("Attempted to rebuild interface but render_state was None. Cannot initialize GPU buffers.");
-            let mut interface_guard = self.interface.lock().unwrap();
-            *interface_guard = new_interface_data;
         }
     }
 
@@ -193,10 +176,12 @@ impl App {
 
         interface.show(|ui| {
             header_componenet(ui);
-            ui.add_textbox("placeholder", [0.5, 0.5], [0.5, 0.5], "#ffffffff");
+            ui.keyed("notes", |ui| {
+                ui.add_textbox("placeholder", [0.5, 0.5], [0.5, 0.5], "#ffffffff");
+            });
         });
 
-        return interface;
+        interface
     }
 }
 
@@ -209,7 +194,17 @@ impl ApplicationHandler for App {
         let interface_arc = Arc::clone(&self.interface);
         
         self.window_ref = Some(window.clone());
-        self.render_state = Some(pollster::block_on(RenderState::new(window.clone(), interface_arc)).unwrap());
+        self.interface.lock().unwrap().set_scale_factor(window.scale_factor() as f32);
+        let mut render_state = pollster::block_on(RenderState::new(window.clone(), interface_arc, RenderConfig::default(), AdapterSelection::default())).unwrap();
+        let adapter_info = render_state.adapter_info();
+        log::info!("Rendering with {} ({:?}, {:?})", adapter_info.name, adapter_info.backend, adapter_info.device_type);
+        if cfg!(debug_assertions) {
+            render_state.enable_shader_hot_reload();
+        }
+        if let Err(e) = render_state.set_sample_count(4) {
+            log::warn!("Drawing without MSAA: {}", e);
+        }
+        self.render_state = Some(render_state);
 
         self.rebuild_interface();
     }
@@ -0,0 +1,373 @@
+use image::RgbaImage;
+
+/// Padding, extrusion and size limits used when packing the texture atlas.
+#[derive(Debug, Clone, Copy)]
+pub struct AtlasConfig {
+    /// Empty pixels left between neighbouring images.
+    pub padding: u32,
+    /// How many times an image's edge pixels are repeated outwards,
+    /// this stops linear filtering from bleeding in neighbouring texels.
+    pub extrusion: u32,
+    /// Largest width or height the atlas may grow to, this should not
+    /// exceed the device's `max_texture_dimension_2d`.
+    pub max_size: u32,
+}
+
+impl Default for AtlasConfig {
+    fn default() -> Self {
+        Self {
+            padding: 2,
+            extrusion: 1,
+            max_size: 2048,
+        }
+    }
+}
+
+/// Where an image was placed by the [SkylinePacker], the position
+/// excludes padding and extrusion and is relative to its page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackedRect {
+    pub page: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SkylineNode {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Bottom-left skyline rectangle packer.
+///
+/// The skyline is the top edge of everything placed so far, each new
+/// rectangle goes where it leaves that edge lowest.
+pub struct SkylinePacker {
+    width: u32,
+    max_height: u32,
+    config: AtlasConfig,
+    skyline: Vec<SkylineNode>,
+    used_height: u32,
+}
+
+impl SkylinePacker {
+    pub fn new(width: u32, config: AtlasConfig) -> Self {
+        Self {
+            width,
+            max_height: config.max_size,
+            config,
+            skyline: vec![SkylineNode { x: 0, y: 0, width }],
+            used_height: 0,
+        }
+    }
+
+    /// Width of the atlas being packed into.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the tallest column of the skyline, this is
+    /// the smallest height the atlas can be cropped to.
+    pub fn used_height(&self) -> u32 {
+        self.used_height
+    }
+
+    /// Places a `width` x `height` image, returns None when it doesn't fit.
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<PackedRect> {
+        let border = self.config.extrusion * 2 + self.config.padding;
+        let slot_width = width + border;
+        let slot_height = height + border;
+
+        let mut best: Option<(usize, u32, u32)> = None;
+        for index in 0..self.skyline.len() {
+            if let Some(y) = self.fit(index, slot_width, slot_height) {
+                let node_width = self.skyline[index].width;
+                let is_better = match best {
+                    None => true,
+                    Some((_, best_y, best_width)) => y < best_y || (y == best_y && node_width < best_width),
+                };
+                if is_better {
+                    best = Some((index, y, node_width));
+                }
+            }
+        }
+
+        let (index, y, _) = best?;
+        let x = self.skyline[index].x;
+        self.add_level(index, x, y + slot_height, slot_width);
+        self.used_height = self.used_height.max(y + slot_height);
+
+        Some(PackedRect {
+            page: 0,
+            x: x + self.config.extrusion,
+            y: y + self.config.extrusion,
+            width,
+            height,
+        })
+    }
+
+    /// Returns the y a slot starting at skyline node `index` would rest on.
+    fn fit(&self, index: usize, slot_width: u32, slot_height: u32) -> Option<u32> {
+        let x = self.skyline[index].x;
+        if x + slot_width > self.width {
+            return None;
+        }
+
+        let mut remaining = slot_width as i64;
+        let mut y = 0;
+        for node in &self.skyline[index..] {
+            if remaining <= 0 {
+                break;
+            }
+            y = y.max(node.y);
+            if y + slot_height > self.max_height {
+                return None;
+            }
+            remaining -= node.width as i64;
+        }
+        Some(y)
+    }
+
+    fn add_level(&mut self, index: usize, x: u32, y: u32, width: u32) {
+        self.skyline.insert(index, SkylineNode { x, y, width });
+
+        // Shrink or remove the nodes now covered by the new one
+        let right = x + width;
+        while index + 1 < self.skyline.len() {
+            let next = &mut self.skyline[index + 1];
+            if next.x >= right {
+                break;
+            }
+            let overlap = right - next.x;
+            if overlap >= next.width {
+                self.skyline.remove(index + 1);
+            } else {
+                next.x += overlap;
+                next.width -= overlap;
+                break;
+            }
+        }
+
+        // Merge neighbours that ended up at the same height
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+/// The layout produced by [pack_rects], every page is `size` pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedAtlas {
+    pub size: [u32; 2],
+    pub pages: u32,
+    /// Placement of each image in input order.
+    pub rects: Vec<PackedRect>,
+}
+
+/// Packs images of the given sizes, growing the atlas width until
+/// everything fits within `config.max_size`. When a single page isn't
+/// enough, `max_size` wide pages are filled one after another.
+/// Returns None if an image is too large for a page on its own.
+pub fn pack_rects(sizes: &[(u32, u32)], config: AtlasConfig) -> Option<PackedAtlas> {
+    let border = config.extrusion * 2 + config.padding;
+    let total_area: u64 = sizes.iter().map(|(w, h)| ((w + border) * (h + border)) as u64).sum();
+    let widest = sizes.iter().map(|(w, _)| w + border).max().unwrap_or(1);
+
+    // Taller images first gives a flatter skyline
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].1.cmp(&sizes[a].1).then(sizes[b].0.cmp(&sizes[a].0)));
+
+    let mut width = ((total_area as f64).sqrt().ceil() as u32).max(widest).next_power_of_two();
+    while width <= config.max_size {
+        let mut packer = SkylinePacker::new(width, config);
+        let mut placed = vec![None; sizes.len()];
+        let mut packed_all = true;
+
+        for &index in &order {
+            match packer.insert(sizes[index].0, sizes[index].1) {
+                Some(rect) => placed[index] = Some(rect),
+                None => {
+                    packed_all = false;
+                    break;
+                }
+            }
+        }
+
+        if packed_all {
+            return Some(PackedAtlas {
+                size: [packer.width(), packer.used_height().max(1)],
+                pages: 1,
+                rects: placed.into_iter().map(|rect| rect.unwrap()).collect(),
+            });
+        }
+        width *= 2;
+    }
+
+    pack_pages(sizes, &order, config)
+}
+
+/// Fills `max_size` pages in `order`, each image goes on the first page with room for it.
+fn pack_pages(sizes: &[(u32, u32)], order: &[usize], config: AtlasConfig) -> Option<PackedAtlas> {
+    let mut packers: Vec<SkylinePacker> = Vec::new();
+    let mut placed = vec![None; sizes.len()];
+
+    for &index in order {
+        let (width, height) = sizes[index];
+        let existing = packers.iter_mut()
+            .enumerate()
+            .find_map(|(page, packer)| packer.insert(width, height).map(|rect| PackedRect { page: page as u32, ..rect }));
+
+        let rect = match existing {
+            Some(rect) => rect,
+            None => {
+                let mut packer = SkylinePacker::new(config.max_size, config);
+                let rect = packer.insert(width, height)?;
+                packers.push(packer);
+                PackedRect { page: packers.len() as u32 - 1, ..rect }
+            }
+        };
+        placed[index] = Some(rect);
+    }
+
+    // Pages share one size, so the tallest decides the height of them all
+    let height = packers.iter().map(|packer| packer.used_height()).max().unwrap_or(1);
+    Some(PackedAtlas {
+        size: [config.max_size, height],
+        pages: packers.len() as u32,
+        rects: placed.into_iter().map(|rect| rect.unwrap()).collect(),
+    })
+}
+
+/// Packs named images into one atlas image, returns the atlas, its page count
+/// and where each image was placed in input order. Pages are stacked top to
+/// bottom in the image.
+pub fn pack_images(images: &[(String, RgbaImage)], config: AtlasConfig) -> Option<(RgbaImage, u32, Vec<PackedRect>)> {
+    let sizes: Vec<(u32, u32)> = images.iter().map(|(_, image)| image.dimensions()).collect();
+    let packed = pack_rects(&sizes, config)?;
+    let [width, height] = packed.size;
+
+    let mut atlas = RgbaImage::new(width, height * packed.pages);
+    for ((_, image), rect) in images.iter().zip(&packed.rects) {
+        let stacked = PackedRect { y: rect.y + rect.page * height, ..*rect };
+        for (x, y, pixel) in image.enumerate_pixels() {
+            atlas.put_pixel(stacked.x + x, stacked.y + y, *pixel);
+        }
+        extrude_edges(&mut atlas, stacked, config.extrusion);
+    }
+
+    Some((atlas, packed.pages, packed.rects))
+}
+
+/// Splits an asset's file stem into its texture name and nine-slice insets.
+/// `button.9.4` gives `button` with 4 texel insets on every side and
+/// `button.9.4.2.4.6` sets them as left, top, right and bottom.
+pub fn nine_slice_from_name(stem: &str) -> (&str, Option<[u32; 4]>) {
+    let Some((name, insets)) = stem.split_once(".9.") else {
+        return (stem, None);
+    };
+
+    let insets: Option<Vec<u32>> = insets.split('.').map(|inset| inset.parse().ok()).collect();
+    match insets.as_deref() {
+        Some(&[inset]) => (name, Some([inset; 4])),
+        Some(&[left, top, right, bottom]) => (name, Some([left, top, right, bottom])),
+        _ => (stem, None),
+    }
+}
+
+/// Repeats the outermost pixels of `rect` outwards by `extrusion` pixels.
+fn extrude_edges(atlas: &mut RgbaImage, rect: PackedRect, extrusion: u32) {
+    let bottom = rect.y + rect.height - 1;
+    let right = rect.x + rect.width - 1;
+
+    for offset in 1..=extrusion {
+        for x in rect.x..=right {
+            atlas.put_pixel(x, rect.y - offset, *atlas.get_pixel(x, rect.y));
+            atlas.put_pixel(x, bottom + offset, *atlas.get_pixel(x, bottom));
+        }
+    }
+
+    // Columns run through the extruded rows so the corners get filled too
+    for offset in 1..=extrusion {
+        for y in rect.y - extrusion..=bottom + extrusion {
+            atlas.put_pixel(rect.x - offset, y, *atlas.get_pixel(rect.x, y));
+            atlas.put_pixel(right + offset, y, *atlas.get_pixel(right, y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::{nine_slice_from_name, pack_images, pack_rects, AtlasConfig};
+
+    #[test]
+    fn packed_rects_do_not_overlap() {
+        let sizes = [(8, 8), (30, 12), (12, 30), (64, 4), (5, 5), (8, 8), (17, 9), (40, 40)];
+        let config = AtlasConfig::default();
+        let packed = pack_rects(&sizes, config).unwrap();
+        let (size, rects) = (packed.size, packed.rects);
+        let border = config.extrusion * 2 + config.padding;
+
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.x + a.width + config.extrusion <= size[0]);
+            assert!(a.y + a.height + config.extrusion <= size[1]);
+            for b in &rects[i + 1..] {
+                let apart = a.x + a.width + border <= b.x
+                    || b.x + b.width + border <= a.x
+                    || a.y + a.height + border <= b.y
+                    || b.y + b.height + border <= a.y;
+                assert!(apart, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_images_are_rejected() {
+        let config = AtlasConfig { max_size: 64, ..Default::default() };
+        assert!(pack_rects(&[(128, 8)], config).is_none());
+    }
+
+    #[test]
+    fn overflow_spills_onto_new_pages() {
+        let config = AtlasConfig { max_size: 64, ..Default::default() };
+        let packed = pack_rects(&[(40, 40), (40, 40), (40, 40), (8, 8)], config).unwrap();
+
+        assert_eq!(packed.pages, 3);
+        assert_eq!(packed.size[0], 64);
+        let pages: Vec<u32> = packed.rects.iter().map(|rect| rect.page).collect();
+        assert_eq!(pages, [0, 1, 2, 0]);
+        for rect in &packed.rects {
+            assert!(rect.y + rect.height + config.extrusion <= packed.size[1]);
+        }
+    }
+
+    #[test]
+    fn edges_are_extruded() {
+        let image = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+        let config = AtlasConfig { extrusion: 2, ..Default::default() };
+        let (atlas, _, rects) = pack_images(&[("red".to_string(), image)], config).unwrap();
+        let rect = rects[0];
+
+        assert_eq!(atlas.get_pixel(rect.x - 2, rect.y - 2), &Rgba([255, 0, 0, 255]));
+        assert_eq!(atlas.get_pixel(rect.x + rect.width + 1, rect.y + rect.height + 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(atlas.get_pixel(rect.x + rect.width + 2, rect.y), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn nine_slice_insets_are_read_from_names() {
+        assert_eq!(nine_slice_from_name("button"), ("button", None));
+        assert_eq!(nine_slice_from_name("button.9.4"), ("button", Some([4; 4])));
+        assert_eq!(nine_slice_from_name("panel.9.1.2.3.4"), ("panel", Some([1, 2, 3, 4])));
+        assert_eq!(nine_slice_from_name("panel.9.1.2"), ("panel.9.1.2", None));
+    }
+}
@@ -1,59 +1,55 @@
-use rendering::user_interface::{elements::UiEvent, UserInterface};
+use rendering::{definitions::{Color, ColorExt}, user_interface::{elements::{Button, Panel, UiEvent}, layout::{Flex, FlexBasis, FlexItem, Justify}, UserInterface}};
 
 pub fn header_componenet(ui: &mut UserInterface) {
     let header_y = 0.01;
-    ui.add_panel(
-        [0.5, header_y], 
-        "#0d1117ff", 
-        [1.0, header_y * 2.0], 
-        "solid",
-        Some(0)
-    );
-
-    // Close Button
-    ui.add_prop_button(
-        [0.99, header_y], 
-        "#5c030300", 
-        [0.02, header_y * 2.0], 
-        Box::new(|| {UiEvent::CloseRequested}), 
-        "solid"
-    );
-    ui.add_icon(
-        [0.99, header_y], 
-        "#ffffffff", 
-        [10.0, 10.0], 
-        "close"
-    );
-
-    // Maximize Button
-    ui.add_prop_button(
-        [0.97, header_y], 
-        "#30363d00", 
-        [0.02, header_y * 2.0], 
-        Box::new(|| {UiEvent::ResizeRequested}), 
-        "solid"
-    );
-
-    ui.add_icon(
-        [0.97, header_y], 
-        "#ffffffff", 
-        [12.0, 12.0], 
-        "maximize"
-    );
-
-    // Minimize Button
-    ui.add_prop_button(
-        [0.95, header_y], 
-        "#30363d00", 
-        [0.02, header_y * 2.0], 
-        Box::new(|| {UiEvent::SetMinimized}), 
-        "solid"
-    );
-
-    ui.add_icon(
-        [0.95, header_y], 
-        "#ffffffff", 
-        [12.0, 12.0], 
-        "minimize"
-    );
-}
\ No newline at end of file
+    let header = Panel::new([0.5, header_y], Color::from_hex("#0d1117ff").into_vec4(), [1.0, header_y * 2.0], "solid");
+    // Fixed width buttons so they never overlap on narrow windows
+    let button_size = FlexItem::new().with_basis(FlexBasis::Pixels(16.0)).with_shrink(0.0);
+
+    ui.panel(header, Some(0), |ui| {
+        ui.row(Flex::new().with_justify(Justify::End), |ui| {
+            ui.flex_item(button_size, |ui| {
+                // Minimize Button
+                header_button(ui, "#30363d00", || UiEvent::SetMinimized, "minimize", [12.0, 12.0]);
+
+                // Maximize Button
+                header_button(ui, "#30363d00", || UiEvent::ResizeRequested, "maximize", [12.0, 12.0]);
+
+                // Close Button
+                header_button(ui, "#5c030300", || UiEvent::CloseRequested, "close", [10.0, 10.0]);
+            });
+        });
+    });
+}
+
+/// A button filling its slot in the header with an icon centered on it.
+fn header_button(ui: &mut UserInterface, color: &str, event: fn() -> UiEvent, icon: &str, icon_size: [f32; 2]) {
+    let button = Button::new([0.5, 0.5], Color::from_hex(color).into_vec4(), [1.0, 1.0], "solid")
+        .with_prop_fn(event);
+    ui.parent(button, None, |ui| {
+        ui.add_icon([0.5, 0.5], "#ffffffff", icon_size, icon);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use rendering::snapshot::SnapshotTest;
+
+    use crate::utils::atlas_generation::load_texture_atlas;
+
+    use super::header_componenet;
+
+    #[test]
+    fn header_layout() {
+        SnapshotTest::new("header", [800, 600], load_texture_atlas()).assert_matches(|ui| {
+            header_componenet(ui);
+        });
+    }
+
+    #[test]
+    fn header_layout_narrow_window() {
+        SnapshotTest::new("header_narrow", [320, 240], load_texture_atlas()).assert_matches(|ui| {
+            header_componenet(ui);
+        });
+    }
+}
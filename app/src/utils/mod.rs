@@ -1,5 +1,8 @@
 pub mod atlas_generation;
+// Compiled into build.rs, only included here so its tests run with the app's.
+#[cfg(test)]
+mod atlas_packer;
 pub mod components;
 pub mod macros;
 pub mod definitions;
-pub mod state_events;
\ No newline at end of file
+pub mod state_events;
@@ -1,9 +1,7 @@
-use std::any;
-
 use rendering::user_interface::elements::{ElementType, InteractionResult, UiEvent};
 use winit::{dpi::{PhysicalPosition, PhysicalSize}, event::{ElementState, MouseButton, WindowEvent}, keyboard::{Key, NamedKey}, platform::modifier_supplement::KeyEventExtModifierSupplement};
 
-use crate::{utils::definitions::Edge, App};
+use crate::{utils::definitions::Edge, App, UI_ZOOM_STEP};
 
 
 pub fn state_normal(app: &mut App, event: &winit::event::WindowEvent, event_loop: &winit::event_loop::ActiveEventLoop) {
@@ -22,8 +20,14 @@ pub fn state_normal(app: &mut App, event: &winit::event::WindowEvent, event_loop
                     rs.resize(size.width, size.height);
                 }
             }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                app.interface.lock().unwrap().set_scale_factor(*scale_factor as f32);
+                needs_rebuild = true;
+            }
+            WindowEvent::ModifiersChanged(modifiers) => app.modifiers = modifiers.state(),
             WindowEvent::RedrawRequested => {
                 if let Some(rs) = app.render_state.as_mut() {
+                    rs.reload_changed_shaders();
                     match rs.render() {
                         Ok(_) => {}
                         Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
@@ -58,10 +62,8 @@ pub fn state_normal(app: &mut App, event: &winit::event::WindowEvent, event_loop
                         },
                         InteractionResult::None => (),
                     }
-                } else if button == &MouseButton::Left && !state.is_pressed() {
-                    if app.resizing {
-                        app.resizing = false;
-                    }
+                } else if button == &MouseButton::Left && !state.is_pressed() && app.resizing {
+                    app.resizing = false;
                 }
             }
             
@@ -86,68 +88,74 @@ pub fn state_normal(app: &mut App, event: &winit::event::WindowEvent, event_loop
                 }
             }
 
-            WindowEvent::KeyboardInput { event, .. } => {
-                if event.state == ElementState::Pressed {
-                    match event.key_without_modifiers() {
-                        Key::Named(named_key) => match named_key {
-                            NamedKey::Space => {
-                                if let Some((selected_id, element_type)) = &app.selected_element {
-                                    let mut interface_guard = app.interface.lock().unwrap();
-                                    for element in &mut interface_guard.elements {
-                                        if *element_type == ElementType::TextBox(*selected_id) {
-                                        //if element.get_id() == *selected_id && *element_type == ElementType::TextBox{
-                                            element.set_text(" ", [app.window_size.width, app.window_size.height]);
-                                            needs_text_update = true;
-                                        }
-                                    }
-                                }
-                            }
-                            NamedKey::Enter => {
-                                if let Some((selected_id, element_type)) = &app.selected_element {
-                                    let mut interface_guard = app.interface.lock().unwrap();
-                                    for element in &mut interface_guard.elements {
-                                        if *element_type == ElementType::TextBox(*selected_id) {
-                                            element.set_text("\n", [app.window_size.width, app.window_size.height]);
-                                            needs_text_update = true;
-                                        }
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed && app.modifiers.control_key() => {
+                match event.key_without_modifiers().as_ref() {
+                    Key::Character("=" | "+") => app.zoom_interface(Some(UI_ZOOM_STEP)),
+                    Key::Character("-") => app.zoom_interface(Some(1.0 / UI_ZOOM_STEP)),
+                    Key::Character("0") => app.zoom_interface(None),
+                    _ => (),
+                }
+            }
+
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                match event.key_without_modifiers() {
+                    Key::Named(named_key) => match named_key {
+                        NamedKey::Space => {
+                            if let Some((selected_id, element_type)) = &app.selected_element {
+                                let mut interface_guard = app.interface.lock().unwrap();
+                                for element in &mut interface_guard.elements {
+                                    if *element_type == ElementType::TextBox(*selected_id) {
+                                    //if element.get_id() == *selected_id && *element_type == ElementType::TextBox{
+                                        element.set_text(" ", [app.window_size.width, app.window_size.height]);
+                                        needs_text_update = true;
                                     }
                                 }
                             }
-                            _ => ()
                         }
-                        Key::Character(char) => {
+                        NamedKey::Enter => {
                             if let Some((selected_id, element_type)) = &app.selected_element {
                                 let mut interface_guard = app.interface.lock().unwrap();
                                 for element in &mut interface_guard.elements {
                                     if *element_type == ElementType::TextBox(*selected_id) {
-                                        element.set_text(&char, [app.window_size.width, app.window_size.height]);
+                                        element.set_text("\n", [app.window_size.width, app.window_size.height]);
                                         needs_text_update = true;
                                     }
                                 }
                             }
-                        },
-                        Key::Unidentified(native_key) => println!("native: {:?}", native_key),
-                        Key::Dead(_) => println!("DEAD"),
+                        }
+                        _ => ()
                     }
+                    Key::Character(char) => {
+                        if let Some((selected_id, element_type)) = &app.selected_element {
+                            let mut interface_guard = app.interface.lock().unwrap();
+                            for element in &mut interface_guard.elements {
+                                if *element_type == ElementType::TextBox(*selected_id) {
+                                    element.set_text(&char, [app.window_size.width, app.window_size.height]);
+                                    needs_text_update = true;
+                                }
+                            }
+                        }
+                    },
+                    Key::Unidentified(native_key) => println!("native: {:?}", native_key),
+                    Key::Dead(_) => println!("DEAD"),
                 }
             }
         _ => ()
     }
 
-    if needs_update {
-        if let Some(rs) = &app.render_state {
-            let mut interface_guard = app.interface.lock().unwrap();
-            interface_guard.initialize_interface_buffers(&rs.device, &rs.queue, [app.window_size.width, app.window_size.height]);
-        }
+    if needs_update && let Some(rs) = &app.render_state {
+        // Only the highlighted elements changed, their instances are all that's written
+        let mut interface_guard = app.interface.lock().unwrap();
+        interface_guard.update_instances(&rs.device, &rs.queue, [app.window_size.width, app.window_size.height]);
     }
 
     let selected_data = app.selected_element.as_ref();
 
-    if needs_text_update || app.selected_element.is_some() && selected_data.clone().unwrap().1 == ElementType::TextBox(selected_data.unwrap().0) {
-        if let Some(rs) = &app.render_state {
-            let mut interface_guard = app.interface.lock().unwrap();
-            interface_guard.update_text(&rs.device, &rs.queue, [app.window_size.width, app.window_size.height]);
-        }
+    if (needs_text_update || app.selected_element.is_some() && selected_data.unwrap().1 == ElementType::TextBox(selected_data.unwrap().0))
+        && let Some(rs) = &app.render_state
+    {
+        let mut interface_guard = app.interface.lock().unwrap();
+        interface_guard.update_text(&rs.device, &rs.queue, [app.window_size.width, app.window_size.height]);
     }
 
     if needs_rebuild {
@@ -158,48 +166,45 @@ pub fn state_normal(app: &mut App, event: &winit::event::WindowEvent, event_loop
 // This state should heavily limit other possible types of interaction.
 pub fn state_resizing(app: &mut App, event: &winit::event::WindowEvent) {
 
-    match event {
-        WindowEvent::CursorMoved { position, .. } => {
-            let window = app.window_ref.clone().unwrap();
-
-            let current_window_size = window.inner_size();
-            let current_window_position = window.outer_position().expect("Failed to get window position.");
+    if let WindowEvent::CursorMoved { position, .. } = event {
+        let window = app.window_ref.clone().unwrap();
 
-            let side = app.handle_resizing( app.cursor_position, [current_window_size.width as f32, current_window_size.height as f32]);
-            let delta_x = position.x as f32 - app.cursor_position[0];
-            let delta_y = position.y as f32 - app.cursor_position[1];
+        let current_window_size = window.inner_size();
+        let current_window_position = window.outer_position().expect("Failed to get window position.");
 
-            let (mut new_width, mut new_height) = (current_window_size.width as f32, current_window_size.height as f32);
-            let mut new_position_x = current_window_position.x;
+        let side = app.handle_resizing( app.cursor_position, [current_window_size.width as f32, current_window_size.height as f32]);
+        let delta_x = position.x as f32 - app.cursor_position[0];
+        let delta_y = position.y as f32 - app.cursor_position[1];
 
-            println!("{:?}", side);
+        let (mut new_width, mut new_height) = (current_window_size.width as f32, current_window_size.height as f32);
+        let mut new_position_x = current_window_position.x;
 
-            match side {
-                Edge::None => (),
+        println!("{:?}", side);
 
-                Edge::Left => {
-                    new_width = (current_window_size.width as f32 - delta_x).max(100.0);
-                    new_position_x = (current_window_position.x as f32 + delta_x) as i32;
-                },
+        match side {
+            Edge::None => (),
 
-                Edge::Right => new_width = (current_window_size.width as f32 + delta_x).max(100.0),
-                Edge::Bottom => new_height = (current_window_size.height as f32 + delta_y).max(100.0),
-                Edge::BottomLeft => {
-                    new_width = (current_window_size.width as f32 - delta_x).max(100.0);
-                    new_height = (current_window_size.height as f32 + delta_y).max(100.0);
+            Edge::Left => {
+                new_width = (current_window_size.width as f32 - delta_x).max(100.0);
+                new_position_x = (current_window_position.x as f32 + delta_x) as i32;
+            },
 
-                    new_position_x = (current_window_position.x as f32 + delta_x) as i32;
-                },
-                Edge::BottomRight => {
-                    new_width = (current_window_size.width as f32 + delta_x).max(100.0);
-                    new_height = (current_window_size.height as f32 + delta_y).max(100.0);
-                },
-            }
+            Edge::Right => new_width = (current_window_size.width as f32 + delta_x).max(100.0),
+            Edge::Bottom => new_height = (current_window_size.height as f32 + delta_y).max(100.0),
+            Edge::BottomLeft => {
+                new_width = (current_window_size.width as f32 - delta_x).max(100.0);
+                new_height = (current_window_size.height as f32 + delta_y).max(100.0);
 
-            window.set_outer_position(PhysicalPosition::new(new_position_x, current_window_position.y));
-            let _ = window.request_inner_size(PhysicalSize::new(new_width as u32, new_height as u32));
-            window.request_redraw();
+                new_position_x = (current_window_position.x as f32 + delta_x) as i32;
+            },
+            Edge::BottomRight => {
+                new_width = (current_window_size.width as f32 + delta_x).max(100.0);
+                new_height = (current_window_size.height as f32 + delta_y).max(100.0);
+            },
         }
-        _ => ()
+
+        window.set_outer_position(PhysicalPosition::new(new_position_x, current_window_position.y));
+        let _ = window.request_inner_size(PhysicalSize::new(new_width as u32, new_height as u32));
+        window.request_redraw();
     }
 }
\ No newline at end of file
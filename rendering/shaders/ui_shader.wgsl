@@ -6,7 +6,7 @@ struct Camera2DUniform {
 var<uniform> camera: Camera2DUniform;
 
 @group(1) @binding(0)
-var texture: texture_2d<f32>;
+var texture: texture_2d_array<f32>;
 
 @group(1) @binding(1)
 var texture_sampler: sampler;
@@ -17,48 +17,207 @@ struct VertexInput {
 };
 
 struct InstanceInput {
-    @location(2) id: u32,
-    @location(3) position_offset: vec2<f32>,
-    @location(4) color: vec4<f32>,
-    @location(5) scale: vec2<f32>,
-    @location(6) atlas_coords: vec4<f32>,
+    @location(2) position_offset: vec2<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) scale: vec2<f32>,
+    @location(5) atlas_coords: vec4<f32>,
+    @location(6) atlas_page: u32,
+    @location(7) depth: f32,
+    // Corner radius, border width and blur radius
+    @location(8) shape: vec4<f32>,
+    @location(9) border_color: vec4<f32>,
+    @location(10) gradient_colors: vec4<u32>,
+    @location(11) gradient_offsets: vec4<f32>,
+    // Kind, stop count and the angle or center
+    @location(12) gradient: vec4<f32>,
+    // Left, top, right and bottom in screen pixels
+    @location(13) clip_rect: vec4<f32>,
+    // Left, top, right and bottom insets in texels
+    @location(14) nine_slice: vec4<f32>,
+    // Columns of the 2x2 rotation and skew matrix
+    @location(15) transform: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) @interpolate(flat) atlas_page: u32,
+    @location(3) quad_uv: vec2<f32>,
+    @location(4) @interpolate(flat) size: vec2<f32>,
+    @location(5) @interpolate(flat) corner_radius: f32,
+    @location(6) @interpolate(flat) border_width: f32,
+    @location(7) @interpolate(flat) border_color: vec4<f32>,
+    @location(8) @interpolate(flat) blur_radius: f32,
+    @location(9) @interpolate(flat) gradient_colors: vec4<u32>,
+    @location(10) @interpolate(flat) gradient_offsets: vec4<f32>,
+    @location(11) @interpolate(flat) gradient: vec4<f32>,
+    @location(12) @interpolate(flat) nine_slice_scale: f32,
+    @location(13) @interpolate(flat) clip_rect: vec4<f32>,
+    @location(14) @interpolate(flat) atlas_coords: vec4<f32>,
+    @location(15) @interpolate(flat) nine_slice: vec4<f32>,
 };
 
 @vertex
 fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
     var out: VertexOutput;
 
+    // The pivot is already folded into the position offset
+    let x_axis = instance.transform.xy * instance.scale.x;
+    let y_axis = instance.transform.zw * instance.scale.y;
     let instance_transformation = mat4x4<f32>(
-        vec4<f32>(instance.scale.x, 0.0, 0.0, 0.0),
-        vec4<f32>(0.0, instance.scale.y, 0.0, 0.0),
+        vec4<f32>(x_axis, 0.0, 0.0),
+        vec4<f32>(y_axis, 0.0, 0.0),
         vec4<f32>(0.0, 0.0, 1.0, 0.0),
         vec4<f32>(instance.position_offset.x, instance.position_offset.y, 0.0, 1.0),
     );
 
     let instance_position = instance_transformation * vec4<f32>(in.position, 0.0, 1.0);
     out.clip_position = camera.view_proj * instance_position;
+    out.clip_position.z = instance.depth * out.clip_position.w;
 
     let atlas_start = instance.atlas_coords.xy;
     let atlas_end = instance.atlas_coords.zw;
     let atlas_size = atlas_end - atlas_start;
     out.tex_coords = atlas_start + in.quad_uv * atlas_size;
+    out.atlas_page = instance.atlas_page;
+    out.quad_uv = in.quad_uv;
+    out.size = instance.scale;
+    out.corner_radius = instance.shape.x;
+    out.border_width = instance.shape.y;
+    out.blur_radius = instance.shape.z;
+    out.border_color = instance.border_color;
+    out.gradient_colors = instance.gradient_colors;
+    out.gradient_offsets = instance.gradient_offsets;
+    out.gradient = instance.gradient;
+    out.clip_rect = instance.clip_rect;
+    out.atlas_coords = instance.atlas_coords;
+    out.nine_slice = instance.nine_slice;
+    out.nine_slice_scale = instance.shape.w;
 
     out.color = instance.color;
     return out;
 }
 
+// How far a shadow's blur reaches past its rectangle, matches SHADOW_BLUR_EXTENT
+const SHADOW_BLUR_EXTENT: f32 = 1.5;
+
+// Signed distance from `p` to a rectangle centered on the origin, negative inside
+fn rounded_rect_sdf(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let q = abs(p) - half_size + radius;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+// Abramowitz and Stegun approximation of the error function
+fn erf(x: f32) -> f32 {
+    let s = sign(x);
+    let a = abs(x);
+    let t = 1.0 + (0.278393 + (0.230389 + 0.078108 * a * a) * a) * a;
+    let t2 = t * t;
+    return s - s / (t2 * t2);
+}
+
+// Color of the instance's gradient at this fragment, white when it has none
+fn gradient_color(in: VertexOutput) -> vec4<f32> {
+    let kind = u32(in.gradient.x);
+    let stop_count = u32(in.gradient.y);
+    if kind == 0u || stop_count == 0u {
+        return vec4<f32>(1.0);
+    }
+
+    var t: f32;
+    if kind == 1u {
+        // Measured in pixels so the angle holds on elements that aren't square
+        let direction = vec2<f32>(cos(in.gradient.z), sin(in.gradient.z));
+        let half_length = dot(abs(direction), in.size * 0.5);
+        t = dot((in.quad_uv - 0.5) * in.size, direction) / (2.0 * half_length) + 0.5;
+    } else {
+        let center = in.gradient.zw;
+        let farthest_corner = max(center, 1.0 - center) * in.size;
+        t = length((in.quad_uv - center) * in.size) / max(length(farthest_corner), 0.0001);
+    }
+
+    var color = unpack4x8unorm(in.gradient_colors[0]);
+    for (var i = 1u; i < stop_count; i++) {
+        let start = in.gradient_offsets[i - 1u];
+        let end = in.gradient_offsets[i];
+        let amount = clamp((t - start) / max(end - start, 0.0001), 0.0, 1.0);
+        color = mix(color, unpack4x8unorm(in.gradient_colors[i]), amount);
+    }
+    return color;
+}
+
+// Maps a pixel along one axis of the element to a texel along the texture,
+// the insets keep their size in texels while the middle stretches
+fn nine_slice_axis(pixel: f32, size: f32, texels: f32, start_inset: f32, end_inset: f32, scale: f32) -> f32 {
+    // Pixels per inset texel, insets shrink together on elements smaller than them
+    let fit = min(scale, size / max(start_inset + end_inset, 0.0001));
+    if pixel < start_inset * fit {
+        return pixel / fit;
+    }
+    if pixel > size - end_inset * fit {
+        return texels - (size - pixel) / fit;
+    }
+    // Kept half a texel away from the insets so filtering doesn't smear them over the middle
+    let middle = (pixel - start_inset * fit) / max(size - (start_inset + end_inset) * fit, 0.0001);
+    return start_inset + 0.5 + middle * max(texels - start_inset - end_inset - 1.0, 0.0);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var final_color: vec4<f32>;
 
-    final_color = textureSample(texture, texture_sampler, in.tex_coords);
-    final_color = final_color * vec4<f32>(in.color);
+    var tex_coords = in.tex_coords;
+    if any(in.nine_slice > vec4<f32>(0.0)) {
+        // Atlas coordinates are inset by half a texel on every side
+        let atlas_size = vec2<f32>(textureDimensions(texture));
+        let region_start = in.atlas_coords.xy - 0.5 / atlas_size;
+        let region_texels = (in.atlas_coords.zw - in.atlas_coords.xy) * atlas_size + 1.0;
+        let pixel = in.quad_uv * in.size;
+        let texel = vec2<f32>(
+            nine_slice_axis(pixel.x, in.size.x, region_texels.x, in.nine_slice.x, in.nine_slice.z, in.nine_slice_scale),
+            nine_slice_axis(pixel.y, in.size.y, region_texels.y, in.nine_slice.y, in.nine_slice.w, in.nine_slice_scale),
+        );
+        tex_coords = clamp(region_start + texel / atlas_size, in.atlas_coords.xy, in.atlas_coords.zw);
+    }
+
+    final_color = textureSample(texture, texture_sampler, tex_coords, in.atlas_page);
+    final_color = final_color * vec4<f32>(in.color) * gradient_color(in);
+
+    let half_size = in.size * 0.5;
+    let radius = min(in.corner_radius, min(half_size.x, half_size.y));
+    let point = (in.quad_uv - 0.5) * in.size;
+    let distance = rounded_rect_sdf(point, half_size, radius);
+    // About a pixel wide however the quad is scaled
+    let edge_width = max(fwidth(distance), 0.0001);
+
+    if in.blur_radius > 0.0 {
+        // The shadow's rectangle convolved with a gaussian, the quad is padded to fit the blur
+        let shadow_half_size = max(half_size - in.blur_radius * SHADOW_BLUR_EXTENT, vec2<f32>(0.0));
+        let shadow_radius = min(in.corner_radius, min(shadow_half_size.x, shadow_half_size.y));
+        let shadow_distance = rounded_rect_sdf(point, shadow_half_size, shadow_radius);
+        let sigma = in.blur_radius * 0.5;
+        final_color.a = final_color.a * (0.5 - 0.5 * erf(shadow_distance / (sigma * sqrt(2.0))));
+    } else if in.corner_radius > 0.0 || in.border_width > 0.0 {
+        // Plain rectangles keep hard edges so panels placed side by side don't show seams
+        if in.border_width > 0.0 {
+            let border = clamp(0.5 + (distance + in.border_width) / edge_width, 0.0, 1.0);
+            final_color = mix(final_color, in.border_color, border);
+        }
+        final_color.a = final_color.a * clamp(0.5 - distance / edge_width, 0.0, 1.0);
+    }
+
+    // Texels too faint to show mustn't hide what's behind them in the depth buffer,
+    // like the fringes of anti-aliased edges
+    if final_color.a < 1.0 / 255.0 {
+        discard;
+    }
+
+    // Clip rects are in screen pixels, so they stay put when a camera moves the content
+    let outside_clip_rect = any(in.clip_position.xy < in.clip_rect.xy) || any(in.clip_position.xy >= in.clip_rect.zw);
+    if outside_clip_rect {
+        discard;
+    }
 
     return final_color;
 }
\ No newline at end of file
@@ -0,0 +1,109 @@
+use std::{env, error::Error};
+
+/// Environment variable [AdapterSelection::default] looks for an adapter name in.
+pub const ADAPTER_NAME_VAR: &str = "WGPU_ADAPTER_NAME";
+
+/// How [RenderState](crate::RenderState) picks the adapter it renders with.
+///
+/// An adapter whose name contains the one given with [AdapterSelection::with_name],
+/// or otherwise the one in the environment variable, is tried first, then the one wgpu picks for the power preference, and
+/// finally a software adapter if the fallback is allowed. The chosen adapter
+/// is available from [RenderState::adapter_info](crate::RenderState::adapter_info).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdapterSelection {
+    pub(crate) power_preference: wgpu::PowerPreference,
+    pub(crate) backends: wgpu::Backends,
+    pub(crate) name: Option<String>,
+    pub(crate) name_var: Option<String>,
+    pub(crate) software_fallback: bool,
+}
+
+impl Default for AdapterSelection {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            backends: wgpu::Backends::all(),
+            name: None,
+            name_var: Some(ADAPTER_NAME_VAR.to_string()),
+            software_fallback: true,
+        }
+    }
+}
+
+impl AdapterSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Only considers adapters from `backends`.
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    /// Prefers an adapter whose name contains `name`, ignoring case. Takes
+    /// precedence over the environment variable.
+    pub fn with_name(mut self, name: Option<&str>) -> Self {
+        self.name = name.map(str::to_string);
+        self
+    }
+
+    /// Reads the preferred adapter's name from the environment variable `name_var`,
+    /// None ignores the environment.
+    pub fn with_name_var(mut self, name_var: Option<&str>) -> Self {
+        self.name_var = name_var.map(str::to_string);
+        self
+    }
+
+    /// Whether a software adapter is tried when no hardware adapter is found.
+    pub fn with_software_fallback(mut self, software_fallback: bool) -> Self {
+        self.software_fallback = software_fallback;
+        self
+    }
+
+    pub(crate) fn create_instance(&self) -> wgpu::Instance {
+        wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        })
+    }
+
+    fn preferred_name(&self) -> Option<String> {
+        self.name.clone()
+            .or_else(|| self.name_var.as_deref().and_then(|name_var| env::var(name_var).ok()))
+            .filter(|name| !name.is_empty())
+    }
+
+    pub(crate) async fn request_adapter(&self, instance: &wgpu::Instance, compatible_surface: Option<&wgpu::Surface<'_>>) -> Result<wgpu::Adapter, Box<dyn Error>> {
+        if let Some(name) = self.preferred_name() {
+            let name = name.to_lowercase();
+            let named = instance.enumerate_adapters(self.backends).into_iter().find(|adapter| {
+                adapter.get_info().name.to_lowercase().contains(&name)
+                    && compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+            });
+            match named {
+                Some(adapter) => return Ok(adapter),
+                None => log::warn!("No adapter named '{name}' was found, picking one by power preference"),
+            }
+        }
+
+        let request = |force_fallback_adapter| instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: self.power_preference,
+            force_fallback_adapter,
+            compatible_surface,
+        });
+        match request(false).await {
+            Ok(adapter) => Ok(adapter),
+            Err(error) if self.software_fallback => {
+                log::warn!("{error}, trying a software adapter");
+                Ok(request(true).await?)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+}
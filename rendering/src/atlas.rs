@@ -0,0 +1,370 @@
+use std::{collections::HashMap, error::Error};
+
+use image::RgbaImage;
+
+/// Handle to a texture added to the atlas at runtime with
+/// [RenderState::add_texture](crate::RenderState::add_texture).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AtlasHandle(u32);
+
+/// A region of one of the atlas pages in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct AtlasRegion {
+    pub(crate) page: u32,
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+/// Empty pixels kept around runtime allocations.
+const PADDING: u32 = 1;
+
+/// Largest page the atlas grows to before starting a new one, kept in line
+/// with the build-time packer so a page is never more than 16MB.
+const MAX_PAGE_SIZE: u32 = 2048;
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Free horizontal spans as `(x, width)`, sorted by x.
+    free: Vec<(u32, u32)>,
+}
+
+/// Shelf allocator for the runtime atlas.
+///
+/// Space is split into rows (shelves) as tall as the first image placed in
+/// them, freed regions are returned to their shelf and merged with neighbours.
+pub(crate) struct ShelfAllocator {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfAllocator {
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Claims the top-left `width` x `height` pixels, used for
+    /// the atlas image that was loaded with the interface.
+    pub(crate) fn reserve(&mut self, width: u32, height: u32) {
+        let mut free = Vec::new();
+        if width < self.width {
+            free.push((width, self.width - width));
+        }
+        self.shelves.push(Shelf { y: 0, height, free });
+    }
+
+    pub(crate) fn allocate(&mut self, width: u32, height: u32) -> Option<AtlasRegion> {
+        let padded_width = width + PADDING;
+        let padded_height = height + PADDING;
+
+        // The shelf wasting the least height wins
+        let mut best: Option<(usize, usize)> = None;
+        for (shelf_index, shelf) in self.shelves.iter().enumerate() {
+            if shelf.height < padded_height {
+                continue;
+            }
+            if let Some(span_index) = shelf.free.iter().position(|&(_, span_width)| span_width >= padded_width) {
+                let is_better = best.is_none_or(|(best_shelf, _)| shelf.height < self.shelves[best_shelf].height);
+                if is_better {
+                    best = Some((shelf_index, span_index));
+                }
+            }
+        }
+
+        let (shelf_index, span_index) = match best {
+            Some(found) => found,
+            None => {
+                let y = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+                if y + padded_height > self.height || padded_width > self.width {
+                    return None;
+                }
+                self.shelves.push(Shelf { y, height: padded_height, free: vec![(0, self.width)] });
+                (self.shelves.len() - 1, 0)
+            }
+        };
+
+        let shelf = &mut self.shelves[shelf_index];
+        let (x, span_width) = shelf.free[span_index];
+        if span_width == padded_width {
+            shelf.free.remove(span_index);
+        } else {
+            shelf.free[span_index] = (x + padded_width, span_width - padded_width);
+        }
+
+        Some(AtlasRegion { page: 0, x, y: shelf.y, width, height })
+    }
+
+    pub(crate) fn deallocate(&mut self, region: AtlasRegion) {
+        let Some(shelf_index) = self.shelves.iter().position(|shelf| shelf.y == region.y) else {
+            return;
+        };
+
+        let shelf = &mut self.shelves[shelf_index];
+        shelf.free.push((region.x, region.width + PADDING));
+        Self::merge_spans(&mut shelf.free);
+
+        // Empty shelves at the bottom are dropped so their height can be reused
+        while let Some(shelf) = self.shelves.last() {
+            if shelf.y != 0 && shelf.free == [(0, self.width)] {
+                self.shelves.pop();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Extends the allocator to a larger texture, existing regions keep their position.
+    pub(crate) fn grow(&mut self, width: u32, height: u32) {
+        for shelf in &mut self.shelves {
+            shelf.free.push((self.width, width - self.width));
+            Self::merge_spans(&mut shelf.free);
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    fn merge_spans(spans: &mut Vec<(u32, u32)>) {
+        spans.sort_by_key(|&(x, _)| x);
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(spans.len());
+        for &(x, width) in spans.iter() {
+            match merged.last_mut() {
+                Some(last) if last.0 + last.1 == x => last.1 += width,
+                _ => merged.push((x, width)),
+            }
+        }
+        *spans = merged;
+    }
+}
+
+/// The atlas texture array on the GPU along with the space left on each page.
+pub(crate) struct AtlasTexture {
+    texture: wgpu::Texture,
+    sampler: wgpu::Sampler,
+    pub(crate) bind_group_layout: wgpu::BindGroupLayout,
+    pub(crate) bind_group: wgpu::BindGroup,
+    /// One allocator per page.
+    allocators: Vec<ShelfAllocator>,
+    regions: HashMap<AtlasHandle, (String, AtlasRegion)>,
+    next_handle: u32,
+}
+
+impl AtlasTexture {
+    /// Uploads the atlas image loaded with the interface,
+    /// `image` holds `pages` pages stacked top to bottom.
+    pub(crate) fn new(device: &wgpu::Device, queue: &wgpu::Queue, image: &RgbaImage, pages: u32) -> Self {
+        let width = image.width();
+        let height = image.height() / pages;
+        // GL can only sample a texture as an array if it has several layers,
+        // so a single baked page gets an empty one for runtime textures
+        let layers = pages.max(2);
+        let texture = Self::create_texture(device, width, height, layers);
+        let page_bytes = (4 * width * height) as usize;
+        for (page, pixels) in image.chunks(page_bytes).enumerate() {
+            Self::write_region(queue, &texture, AtlasRegion { page: page as u32, x: 0, y: 0, width, height }, pixels);
+        }
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None
+                    }
+                ],
+                label: Some("texture_bind_group_layout"),
+            });
+
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &texture, &sampler);
+
+        // Pages baked at build time are treated as full
+        let allocators = (0..layers).map(|page| {
+            let mut allocator = ShelfAllocator::new(width, height);
+            if page < pages {
+                allocator.reserve(width, height);
+            }
+            allocator
+        }).collect();
+
+        Self {
+            texture,
+            sampler,
+            bind_group_layout,
+            bind_group,
+            allocators,
+            regions: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// Size of a single page.
+    pub(crate) fn size(&self) -> [u32; 2] {
+        [self.texture.width(), self.texture.height()]
+    }
+
+    pub(crate) fn pages(&self) -> u32 {
+        self.texture.depth_or_array_layers()
+    }
+
+    /// Allocates space for `image` and uploads it. When no page has room the
+    /// pages are doubled in size, once they reach [MAX_PAGE_SIZE] or the device
+    /// limit a new page is added instead.
+    pub(crate) fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, name: &str, image: &RgbaImage) -> Result<(AtlasHandle, AtlasRegion), Box<dyn Error>> {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(format!("Texture '{name}' is empty").into());
+        }
+        let max_size = MAX_PAGE_SIZE.min(device.limits().max_texture_dimension_2d);
+        if width + PADDING > max_size || height + PADDING > max_size {
+            return Err(format!("Texture '{name}' is {width}x{height}, larger than a {max_size}x{max_size} atlas page").into());
+        }
+
+        let region = loop {
+            let allocated = self.allocators.iter_mut()
+                .enumerate()
+                .find_map(|(page, allocator)| {
+                    allocator.allocate(width, height).map(|region| AtlasRegion { page: page as u32, ..region })
+                });
+            if let Some(region) = allocated {
+                break region;
+            }
+
+            let [current_width, current_height] = self.size();
+            if current_width < max_size || current_height < max_size {
+                self.resize(device, queue, (current_width * 2).min(max_size), (current_height * 2).min(max_size), self.pages());
+            } else if self.pages() < device.limits().max_texture_array_layers {
+                self.resize(device, queue, current_width, current_height, self.pages() + 1);
+            } else {
+                return Err(format!("No room for a {width}x{height} texture in {} atlas pages", self.pages()).into());
+            }
+        };
+
+        Self::write_region(queue, &self.texture, region, image);
+
+        let handle = AtlasHandle(self.next_handle);
+        self.next_handle += 1;
+        self.regions.insert(handle, (name.to_string(), region));
+
+        Ok((handle, region))
+    }
+
+    /// Frees the space used by `handle`, returning the name it was added under.
+    pub(crate) fn remove(&mut self, handle: AtlasHandle) -> Option<String> {
+        let (name, region) = self.regions.remove(&handle)?;
+        self.allocators[region.page as usize].deallocate(region);
+        Some(name)
+    }
+
+    /// Recreates the texture array with larger pages or more of them,
+    /// copying every existing page across.
+    fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32, pages: u32) {
+        let texture = Self::create_texture(device, width, height, pages);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Atlas resize encoder")
+        });
+        encoder.copy_texture_to_texture(self.texture.as_image_copy(), texture.as_image_copy(), self.texture.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        for allocator in &mut self.allocators {
+            allocator.grow(width, height);
+        }
+        self.allocators.resize_with(pages as usize, || ShelfAllocator::new(width, height));
+
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, &texture, &self.sampler);
+        self.texture = texture;
+    }
+
+    fn create_texture(device: &wgpu::Device, width: u32, height: u32, pages: u32) -> wgpu::Texture {
+        device.create_texture(
+            &wgpu::TextureDescriptor {
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: pages,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+                label: Some("diffuse_texture"),
+                view_formats: &[],
+            }
+        )
+    }
+
+    fn create_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, texture: &wgpu::Texture, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+        // A single page would otherwise default to a plain 2D view
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        device.create_bind_group(
+        &wgpu::BindGroupDescriptor {
+                label: Some("GUI Material Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    }
+                ],
+            }
+        )
+    }
+
+    fn write_region(queue: &wgpu::Queue, texture: &wgpu::Texture, region: AtlasRegion, pixels: &[u8]) {
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: region.x, y: region.y, z: region.page },
+                aspect: wgpu::TextureAspect::All,
+            },
+            pixels,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * region.width),
+                rows_per_image: Some(region.height),
+            },
+            wgpu::Extent3d {
+                width: region.width,
+                height: region.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
@@ -7,19 +7,85 @@ pub(crate) struct Camera2DUniform {
     pub(crate) view_proj: [[f32; 4]; 4],
 }
 
-pub(crate) struct Camera2D {
+/// Handle to a camera added to the interface with
+/// [Interface::add_camera](crate::user_interface::interface::Interface::add_camera).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CameraHandle(pub(crate) usize);
+
+/// Maps world coordinates to pixels on screen. World units are logical pixels,
+/// at zoom 1 one of them covers `scale_factor` physical pixels. Screen points
+/// are physical pixels, the same as the cursor positions winit reports.
+///
+/// The interface is drawn with a fixed camera, zoomable views get their own
+/// with [Interface::add_camera](crate::user_interface::interface::Interface::add_camera)
+/// and show elements through it with [UserInterface::canvas](crate::user_interface::UserInterface::canvas).
+#[derive(Debug, Clone)]
+pub struct Camera2D {
+    /// World point drawn at the top left corner of the screen.
     position: Vec2,
+    zoom: f32,
+    /// Physical pixels per logical pixel, see [Interface::set_scale_factor](crate::user_interface::interface::Interface::set_scale_factor).
+    scale_factor: f32,
     screen_size: PhysicalSize<u32>,
 }
 
 impl Camera2D {
-    pub(crate) fn new(screen_width: u32, screen_height: u32) -> Self {
-        Self { 
-            position: Vec2::new(0.0, 0.0), 
+    pub fn new(screen_width: u32, screen_height: u32) -> Self {
+        Self {
+            position: Vec2::new(0.0, 0.0),
+            zoom: 1.0,
+            scale_factor: 1.0,
             screen_size: PhysicalSize::new(screen_width, screen_height),
         }
     }
 
+    pub fn position(&self) -> [f32; 2] {
+        self.position.into()
+    }
+
+    pub fn set_position(&mut self, position: [f32; 2]) {
+        self.position = position.into();
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets how many pixels one world unit covers, it's kept above zero.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.max(f32::EPSILON);
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Physical pixels one world unit covers on screen.
+    fn pixels_per_unit(&self) -> f32 {
+        self.zoom * self.scale_factor
+    }
+
+    /// Moves the view so the world follows a drag of `screen_delta` pixels.
+    pub fn pan(&mut self, screen_delta: [f32; 2]) {
+        self.position -= Vec2::from(screen_delta) / self.pixels_per_unit();
+    }
+
+    /// Multiplies the zoom by `factor`, the world point under `screen_point`
+    /// stays where it is on screen.
+    pub fn zoom_at(&mut self, factor: f32, screen_point: [f32; 2]) {
+        let anchor = Vec2::from(self.screen_to_world(screen_point));
+        self.set_zoom(self.zoom * factor);
+        self.position = anchor - Vec2::from(screen_point) / self.pixels_per_unit();
+    }
+
+    pub fn screen_to_world(&self, screen_point: [f32; 2]) -> [f32; 2] {
+        (Vec2::from(screen_point) / self.pixels_per_unit() + self.position).into()
+    }
+
+    pub fn world_to_screen(&self, world_point: [f32; 2]) -> [f32; 2] {
+        ((Vec2::from(world_point) - self.position) * self.pixels_per_unit()).into()
+    }
+
     fn build_projection_matrix(&self) -> Mat4 {
         let width = self.screen_size.width as f32;
         let height = self.screen_size.height as f32;
@@ -34,16 +100,20 @@ impl Camera2D {
         )
     }
 
-    fn build_view_matrix(&self) -> Mat4 {
-        Mat4::from_translation(Vec3::new(-self.position.x, -self.position.y, 0.0))
+    pub(crate) fn build_view_matrix(&self) -> Mat4 {
+        Mat4::from_scale(Vec3::new(self.pixels_per_unit(), self.pixels_per_unit(), 1.0))
+            * Mat4::from_translation(Vec3::new(-self.position.x, -self.position.y, 0.0))
     }
 
     pub(crate) fn build_view_projection_matrix(&self) -> Mat4 {
-        let view_proj = self.build_projection_matrix() * self.build_view_matrix();
-        view_proj
+        self.build_projection_matrix() * self.build_view_matrix()
+    }
+
+    pub(crate) fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
     }
 
     pub(crate) fn update_screen_size(&mut self, new_size: PhysicalSize<u32>) {
         self.screen_size = new_size;
     }
-}
\ No newline at end of file
+}
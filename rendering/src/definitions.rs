@@ -1,3 +1,8 @@
+use std::{error::Error, sync::Arc};
+
+use image::RgbaImage;
+use serde::Deserialize;
+
 #[repr(C)]
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy)]
 pub struct Vertex {
@@ -51,21 +56,68 @@ impl Instance {
             position: self.position,
             color: self.color,
             scale: self.scale,
-            tex_coords: [0.0, 0.0, 0.0, 0.0]
+            tex_coords: [0.0, 0.0, 0.0, 0.0],
+            atlas_page: 0,
+            depth: 0.0,
+            shape: [0.0, 0.0, 0.0, 1.0],
+            border_color: [0.0; 4],
+            gradient_colors: [0; 4],
+            gradient_offsets: [0.0; 4],
+            gradient: [0.0; 4],
+            clip_rect: [f32::MIN, f32::MIN, f32::MAX, f32::MAX],
+            nine_slice: [0.0; 4],
+            transform: [1.0, 0.0, 0.0, 1.0],
         }
     }
 }
 
 #[repr(C)]
-#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, PartialEq)]
 pub struct InstanceRaw {
+    /// Not read by the shader, only 16 vertex attributes are available
+    /// so it isn't part of [InstanceRaw::desc].
     pub id: u32,
     position: [f32; 2],
     color: [f32; 4],
     scale: [f32; 2],
     pub tex_coords: [f32; 4],
+    /// Layer of the atlas texture array `tex_coords` refer to.
+    pub atlas_page: u32,
+    /// Depth buffer value between 0.0 (front) and 1.0 (back).
+    pub depth: f32,
+    /// Corner radius, border width and blur radius in pixels, then the pixels
+    /// a nine-slice inset texel covers. The corner radius is clamped to half the
+    /// shorter side and borders are drawn inside the element's edge. A non-zero
+    /// blur radius marks a shadow, its quad is padded by [SHADOW_BLUR_EXTENT]
+    /// times the blur radius on every side to fit the blur.
+    pub shape: [f32; 4],
+    pub border_color: [f32; 4],
+    /// Colors of up to [MAX_GRADIENT_STOPS] gradient stops, packed as RGBA8.
+    pub gradient_colors: [u32; 4],
+    /// Where each gradient stop sits between 0.0 and 1.0.
+    pub gradient_offsets: [f32; 4],
+    /// Gradient kind (0 for none, 1 linear, 2 radial), the number of stops
+    /// and either the angle in radians or the center of a radial gradient.
+    pub gradient: [f32; 4],
+    /// Fragments outside `[left, top, right, bottom]` in screen pixels are discarded.
+    pub clip_rect: [f32; 4],
+    /// Nine-slice insets of the texture in texels, all zero to stretch it.
+    pub nine_slice: [f32; 4],
+    /// Columns of the rotation and skew applied around `position`,
+    /// which already accounts for the element's pivot.
+    pub transform: [f32; 4],
 }
 
+/// How far a shadow's blur reaches past its rectangle, as a multiple of the
+/// blur radius. Must match `ui_shader.wgsl`.
+pub const SHADOW_BLUR_EXTENT: f32 = 1.5;
+
+/// The most stops a gradient can have, they're stored per instance.
+pub const MAX_GRADIENT_STOPS: usize = 4;
+
+/// Text size of labels and text boxes in logical pixels, unless they set their own.
+pub const DEFAULT_TEXT_SIZE: f32 = 30.0;
+
 impl InstanceRaw {
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
@@ -74,27 +126,72 @@ impl InstanceRaw {
             attributes: &[
                 wgpu::VertexAttribute {
                     shader_location: 2,
-                    offset: 0,
-                    format: wgpu::VertexFormat::Uint32,
+                    offset: std::mem::offset_of!(InstanceRaw, position) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x2,
                 },
                 wgpu::VertexAttribute {
                     shader_location: 3,
-                    offset: std::mem::size_of::<u32>() as wgpu::BufferAddress,
-                    format: wgpu::VertexFormat::Float32x2,
+                    offset: std::mem::offset_of!(InstanceRaw, color) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
                 },
                 wgpu::VertexAttribute {
                     shader_location: 4,
-                    offset: std::mem::size_of::<u32>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
-                    format: wgpu::VertexFormat::Float32x4,
+                    offset: std::mem::offset_of!(InstanceRaw, scale) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x2,
                 },
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<u32>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
                     shader_location: 5,
-                    format: wgpu::VertexFormat::Float32x2,
+                    offset: std::mem::offset_of!(InstanceRaw, tex_coords) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
                 },
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<u32>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress + std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
                     shader_location: 6,
+                    offset: std::mem::offset_of!(InstanceRaw, atlas_page) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 7,
+                    offset: std::mem::offset_of!(InstanceRaw, depth) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 8,
+                    offset: std::mem::offset_of!(InstanceRaw, shape) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 9,
+                    offset: std::mem::offset_of!(InstanceRaw, border_color) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 10,
+                    offset: std::mem::offset_of!(InstanceRaw, gradient_colors) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Uint32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 11,
+                    offset: std::mem::offset_of!(InstanceRaw, gradient_offsets) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 12,
+                    offset: std::mem::offset_of!(InstanceRaw, gradient) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 13,
+                    offset: std::mem::offset_of!(InstanceRaw, clip_rect) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 14,
+                    offset: std::mem::offset_of!(InstanceRaw, nine_slice) as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    shader_location: 15,
+                    offset: std::mem::offset_of!(InstanceRaw, transform) as wgpu::BufferAddress,
                     format: wgpu::VertexFormat::Float32x4,
                 },
             ],
@@ -109,11 +206,23 @@ pub enum GeometryType {
 }
 
 
-#[derive(Debug, Clone)]
+/// Describes where each named texture lives in the atlas image.
+/// 
+/// A manifest is the JSON form of this struct, without the image
+/// or the generated texture coordinates.
+///
+/// The atlas can span several pages of `width` x `height`, these are
+/// uploaded as layers of a texture array. In the image they are stacked
+/// top to bottom, so an atlas with `pages` pages is `height * pages` tall.
+#[derive(Debug, Clone, Deserialize)]
 pub struct UiAtlas {
     pub entries: Vec<UiAtlasTexture>,
     width: u32,
     height: u32,
+    #[serde(default = "UiAtlas::single_page")]
+    pages: u32,
+    #[serde(skip)]
+    image: Option<Arc<RgbaImage>>,
 }
 
 impl UiAtlas {
@@ -121,27 +230,116 @@ impl UiAtlas {
         Self {
             entries: Vec::new(),
             width,
-            height
+            height,
+            pages: 1,
+            image: None,
         }
     }
 
+    fn single_page() -> u32 {
+        1
+    }
+
+    /// Creates an empty atlas backed by `image`, entries
+    /// are then added with [UiAtlas::add_entry].
+    pub fn from_image(image: RgbaImage) -> Self {
+        Self::from_pages(image, 1)
+    }
+
+    /// Creates an empty atlas from an image holding `pages` pages
+    /// stacked vertically, the image height must divide evenly.
+    pub fn from_pages(image: RgbaImage, pages: u32) -> Self {
+        assert!(pages > 0 && image.height().is_multiple_of(pages), "Atlas image can't be split into {pages} pages");
+        let mut atlas = Self::new(image.width(), image.height() / pages);
+        atlas.pages = pages;
+        atlas.image = Some(Arc::new(image));
+        atlas
+    }
+
+    /// Loads an atlas from its encoded image and JSON manifest,
+    /// failing if the two don't describe the same size.
+    pub fn load(image_bytes: &[u8], manifest: &str) -> Result<Self, Box<dyn Error>> {
+        let image = image::load_from_memory(image_bytes)?.to_rgba8();
+        let manifest: UiAtlas = serde_json::from_str(manifest)?;
+
+        if manifest.pages == 0 || image.dimensions() != (manifest.width, manifest.height * manifest.pages) {
+            return Err(format!(
+                "Atlas manifest is {} pages of {}x{} but the image is {}x{}",
+                manifest.pages, manifest.width, manifest.height, image.width(), image.height()
+            ).into());
+        }
+
+        let mut atlas = Self::from_pages(image, manifest.pages);
+        for entry in manifest.entries {
+            atlas.add_entry(entry);
+        }
+        Ok(atlas)
+    }
+
     pub fn add_entry(&mut self, entry: UiAtlasTexture) {
         self.entries.push(entry.generate_tex_coords(self.width, self.height));
     }
 
+    /// Size of a single page.
+    pub fn size(&self) -> [u32; 2] {
+        [self.width, self.height]
+    }
+
+    pub fn pages(&self) -> u32 {
+        self.pages
+    }
+
+    /// Removes the entry called `name`, returning whether it existed.
+    pub fn remove_entry(&mut self, name: &str) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.len() != count
+    }
+
+    /// Updates the atlas size after the texture was grown or gained pages,
+    /// regenerating every entry's texture coordinates.
+    pub fn resize(&mut self, width: u32, height: u32, pages: u32) {
+        self.pages = pages;
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        self.width = width;
+        self.height = height;
+        self.entries = self.entries
+            .drain(..)
+            .map(|entry| entry.generate_tex_coords(width, height))
+            .collect();
+    }
+
     pub fn get_entry_by_name(&self, name: String) -> Option<UiAtlasTexture> {
         self.entries.iter().find(|entry| entry.name == name).cloned()
     }
+
+    /// Returns the pixels the entries refer to, if the atlas was created with them.
+    /// Textures added at runtime only live on the GPU and aren't part of this image.
+    pub fn image(&self) -> Option<&RgbaImage> {
+        self.image.as_deref()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct UiAtlasTexture {
     pub name: String,
     x_start: u32,
     y_start: u32,
     image_width: u32,
     image_height: u32,
+    /// Page of the atlas the texture is on, coordinates are relative to it.
+    #[serde(default)]
+    pub page: u32,
+    /// Nine-slice insets in texels as `[left, top, right, bottom]`. The corners
+    /// they cut off keep their size while the edges and center stretch.
+    #[serde(default)]
+    pub nine_slice: Option<[u32; 4]>,
+    #[serde(skip)]
     pub start_coord: Option<(f32, f32)>,
+    #[serde(skip)]
     pub end_coord: Option<(f32, f32)>
 }
 
@@ -153,11 +351,23 @@ impl UiAtlasTexture {
             y_start: y_0,
             image_width,
             image_height,
+            page: 0,
+            nine_slice: None,
             start_coord: None,
             end_coord: None,
         }
     }
 
+    pub fn with_page(mut self, page: u32) -> Self {
+        self.page = page;
+        self
+    }
+
+    pub fn with_nine_slice(mut self, insets: [u32; 4]) -> Self {
+        self.nine_slice = Some(insets);
+        self
+    }
+
     fn generate_tex_coords(mut self, width: u32, height: u32) -> Self {
         // Calculate a half-pixel offset based on the atlas dimensions
         let half_pixel_x = 0.5 / width as f32;
@@ -1,74 +1,219 @@
-use std::{error::Error, sync::{Arc, Mutex}};
+use std::{collections::HashMap, error::Error, path::Path, sync::{mpsc, Arc, Mutex}};
 
 use wgpu::util::DeviceExt;
 use winit::{dpi::PhysicalSize, window::Window};
 
-use crate::{camera::{Camera2D, Camera2DUniform}, definitions::{ColorExt, InstanceRaw, Vertex}, pipeline::PipeLineBuilder, user_interface::interface::Interface};
+use crate::{adapter::AdapterSelection, atlas::{AtlasHandle, AtlasTexture}, camera::{Camera2D, Camera2DUniform}, definitions::{ColorExt, InstanceRaw, UiAtlasTexture, Vertex}, material::Material, render_config::RenderConfig, pipeline::{PipeLineBuilder, PipelineRegistry, ShaderCode, ShaderWatcher, DEFAULT_PIPELINE, DEPTH_FORMAT, TRANSLUCENT_PIPELINE, translucent_pipeline_name}, user_interface::interface::Interface};
 
-mod camera;
+pub mod adapter;
+pub mod camera;
 mod pipeline;
+pub mod atlas;
 pub mod definitions;
+pub mod material;
+pub mod render_config;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
 pub mod user_interface;
 
+const UI_SHADER: &str = include_str!("../shaders/ui_shader.wgsl");
+
+/// The ui shader in the source tree, only read when hot reloading is enabled.
+const UI_SHADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/ui_shader.wgsl");
+
+/// Where a [RenderState] draws its frames to.
+enum RenderTarget {
+    /// A window's swapchain, presented after every frame.
+    Surface(wgpu::Surface<'static>),
+    /// A texture that frames are rendered into and read back from,
+    /// used when no window or display is available.
+    Offscreen(wgpu::Texture),
+}
+
 pub struct RenderState {
     interface_arc: Arc<Mutex<Interface>>,
 
     pub window_size: PhysicalSize<u32>,
-    surface: wgpu::Surface<'static>,
+    target: RenderTarget,
+    /// Multisampled color target that resolves into the frame, None without MSAA.
+    msaa_view: Option<wgpu::TextureView>,
+    depth_view: wgpu::TextureView,
+    sample_count: u32,
+    adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
+    render_config: RenderConfig,
 
     camera: Camera2D,
     camera_buffer: wgpu::Buffer,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
     camera_bind_group: wgpu::BindGroup,
 
-    texture_bind_group: wgpu::BindGroup,
+    atlas: AtlasTexture,
 
-    ui_pipeline: wgpu::RenderPipeline,
+    pipelines: PipelineRegistry,
+    materials: Vec<Material>,
+    material_bind_group_layout: wgpu::BindGroupLayout,
+    material_uniforms: HashMap<String, wgpu::Buffer>,
+    shader_watcher: Option<ShaderWatcher>,
 
     surface_configured: bool,
 }
 
 impl RenderState {
-    pub async fn new(window: Arc<Window>, interface_arc: Arc<Mutex<Interface>>) -> Result<RenderState, Box<dyn Error>> {
+    pub async fn new(
+        window: Arc<Window>,
+        interface_arc: Arc<Mutex<Interface>>,
+        render_config: RenderConfig,
+        adapter_selection: AdapterSelection,
+    ) -> Result<RenderState, Box<dyn Error>> {
         let window_size = window.inner_size();
 
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let instance = adapter_selection.create_instance();
         let surface = instance.create_surface(window.clone())?;
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptionsBase { 
-            power_preference: wgpu::PowerPreference::HighPerformance, 
-            force_fallback_adapter: false, 
-            compatible_surface: Some(&surface) 
-        }).await?;
+        let adapter = adapter_selection.request_adapter(&instance, Some(&surface)).await?;
 
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
-            label: None,
-            required_features: wgpu::Features::empty(),
-            required_limits: wgpu::Limits::defaults(),
-            memory_hints: Default::default(),
-            trace: wgpu::Trace::Off,
-        }).await?;
+        let (device, queue) = Self::request_device(&adapter).await?;
 
         let surface_capabilities = surface.get_capabilities(&adapter);
-
-        let surface_format = surface_capabilities
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_capabilities.formats[0]);
-
-        let config = wgpu::SurfaceConfiguration {
+        let mut config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            format: surface_capabilities.formats[0],
             width: window_size.width,
             height: window_size.height,
-            present_mode: surface_capabilities.present_modes[0],
+            present_mode: wgpu::PresentMode::Fifo,
             alpha_mode: surface_capabilities.alpha_modes[0],
             desired_maximum_frame_latency: 2,
             view_formats: vec![],
         };
+        render_config.negotiate(&surface_capabilities, &mut config);
+
+        let mut render_state = Self::with_target(interface_arc, RenderTarget::Surface(surface), adapter, device, queue, config, false)?;
+        render_state.render_config = render_config;
+        Ok(render_state)
+    }
+
+    /// Creates a [RenderState] that renders into an offscreen texture of the given
+    /// size instead of a window surface. Frames are read back with [RenderState::read_frame].
+    /// 
+    /// If no hardware adapter is available this falls back to a software adapter,
+    /// so it can be used on machines without a GPU or display.
+    pub async fn new_headless(window_size: PhysicalSize<u32>, interface_arc: Arc<Mutex<Interface>>) -> Result<RenderState, Box<dyn Error>> {
+        Self::new_headless_with_adapter(window_size, interface_arc, AdapterSelection::default()).await
+    }
+
+    /// Like [RenderState::new_headless], picking the adapter with `adapter_selection`.
+    pub async fn new_headless_with_adapter(
+        window_size: PhysicalSize<u32>,
+        interface_arc: Arc<Mutex<Interface>>,
+        adapter_selection: AdapterSelection,
+    ) -> Result<RenderState, Box<dyn Error>> {
+        let instance = adapter_selection.create_instance();
+        let adapter = adapter_selection.request_adapter(&instance, None).await?;
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: window_size.width.max(1),
+            height: window_size.height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            desired_maximum_frame_latency: 2,
+            view_formats: vec![],
+        };
+
+        let texture = Self::create_offscreen_texture(&device, &config);
+
+        Self::with_target(interface_arc, RenderTarget::Offscreen(texture), adapter, device, queue, config, true)
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+        adapter.request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            // Lets the adapter's own list of supported MSAA sample counts be used
+            required_features: adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            required_limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        }).await
+    }
+
+    fn create_offscreen_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Render Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        })
+    }
+
+    /// The depth buffer elements are layered with, it always matches the target's size.
+    fn create_depth_view(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32) -> wgpu::TextureView {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// The color target frames are drawn into before being resolved into
+    /// the surface or offscreen texture, only needed with MSAA.
+    fn create_msaa_view(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32) -> Option<wgpu::TextureView> {
+        if sample_count == 1 {
+            return None;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled Color Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    /// Builds the camera, atlas texture and ui pipeline shared by
+    /// both the windowed and headless render targets.
+    fn with_target(
+        interface_arc: Arc<Mutex<Interface>>,
+        target: RenderTarget,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        config: wgpu::SurfaceConfiguration,
+        surface_configured: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let window_size = PhysicalSize::new(config.width, config.height);
+        let depth_view = Self::create_depth_view(&device, &config, 1);
 
         let camera = Camera2D::new(window_size.width, window_size.height);
         let camera_uniform = Camera2DUniform {
@@ -105,136 +250,379 @@ impl RenderState {
             ] 
         });
 
-        let diffuse_bytes = include_bytes!("../../app/atlas.png");
-        let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
-        let diffuse_rgba = diffuse_image.to_rgba8();
-
-        use image::GenericImageView;
-        let dimensions = diffuse_image.dimensions();
-
-        let texture_size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
+        let (atlas_image, atlas_pages) = {
+            let interface_guard = interface_arc.lock().unwrap();
+            let ui_atlas = interface_guard.atlas();
+            let image = ui_atlas.image()
+                .cloned()
+                .ok_or("The interface's atlas has no image to upload")?;
+            (image, ui_atlas.pages())
         };
-        
-        let diffuse_texture = device.create_texture(
-            &wgpu::TextureDescriptor {
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                label: Some("diffuse_texture"),
-                view_formats: &[],
-            }
-        );
-
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &diffuse_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &diffuse_rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            texture_size,
-        );
+        let atlas = AtlasTexture::new(&device, &queue, &atlas_image, atlas_pages);
 
-        let diffuse_texture_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
-
-        let gui_material_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None
-                    }
-                ],
-                label: Some("texture_bind_group_layout"),
-            });
-
-        let gui_material_bind_group = device.create_bind_group(
-        &wgpu::BindGroupDescriptor {
-                label: Some("GUI Material Bind Group"),
-                layout: &gui_material_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&diffuse_texture_view),
+        let material_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
                     },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&diffuse_sampler),
-                    }
-                ],
-            }
-        );
-
-        let ui_pipeline = PipeLineBuilder::new(&device)
-            .set_pixel_format(wgpu::TextureFormat::Bgra8UnormSrgb)
-            .set_shader_module("rendering/shaders/ui_shader.wgsl", "vs_main", "fs_main")
-            .add_vertex_buffer_layout(Vertex::description())
-            .add_vertex_buffer_layout(InstanceRaw::desc())
-            .add_bind_group_layout(&camera_bind_group_layout)
-            .add_bind_group_layout(&gui_material_bind_group_layout)
-            .build("Render Pipeline");
+                    count: None
+                }
+            ],
+            label: Some("Material Bind Group Layout"),
+        });
 
+        let mut pipelines = PipelineRegistry::new();
+        Self::register_pipeline(&device, config.format, 1, &[&camera_bind_group_layout, &atlas.bind_group_layout], &ShaderCode::Embedded(UI_SHADER), None, &mut pipelines)?;
 
         Ok(Self {
             interface_arc,
 
             window_size,
-            surface,
+            target,
+            msaa_view: None,
+            depth_view,
+            sample_count: 1,
+            adapter,
             device,
             queue,
             config,
+            render_config: RenderConfig::default(),
 
             camera,
             camera_buffer,
+            camera_bind_group_layout,
             camera_bind_group,
 
-            texture_bind_group: gui_material_bind_group,
+            atlas,
 
-            ui_pipeline,
+            pipelines,
+            materials: Vec::new(),
+            material_bind_group_layout,
+            material_uniforms: HashMap::new(),
+            shader_watcher: None,
 
-            surface_configured: false
+            surface_configured
         })
     }
 
+    /// Builds a pipeline for the interface's instances and registers it,
+    /// under the material's name if there is one or as the default otherwise.
+    /// The default and translucent materials also get a variant that doesn't
+    /// write depth, see [translucent_pipeline_name].
+    fn register_pipeline(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        shader: &ShaderCode,
+        material: Option<&Material>,
+        pipelines: &mut PipelineRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        let builder = || {
+            let mut builder = PipeLineBuilder::new(device);
+            builder
+                .set_pixel_format(format)
+                .set_sample_count(sample_count)
+                .add_vertex_buffer_layout(Vertex::description())
+                .add_vertex_buffer_layout(InstanceRaw::desc());
+            for layout in bind_group_layouts {
+                builder.add_bind_group_layout(layout);
+            }
+            builder
+        };
+
+        match material {
+            Some(material) => {
+                builder()
+                    .set_shader_module(shader.clone(), "vs_main", &material.fragment_entry)
+                    .append_shader_source(&material.fragment_source)
+                    .register(&material.name, pipelines)?;
+                let translucent_name = translucent_pipeline_name(Some(&material.name));
+                if !material.translucent {
+                    pipelines.remove(&translucent_name);
+                    return Ok(());
+                }
+                builder()
+                    .set_shader_module(shader.clone(), "vs_main", &material.fragment_entry)
+                    .append_shader_source(&material.fragment_source)
+                    .set_depth_write(false)
+                    .register(&translucent_name, pipelines)
+            }
+            None => {
+                builder()
+                    .set_shader_module(shader.clone(), "vs_main", "fs_main")
+                    .register(DEFAULT_PIPELINE, pipelines)?;
+                builder()
+                    .set_shader_module(shader.clone(), "vs_main", "fs_main")
+                    .set_depth_write(false)
+                    .register(TRANSLUCENT_PIPELINE, pipelines)
+            }
+        }
+    }
+
+    fn register_material_pipeline(&mut self, material: &Material) -> Result<(), Box<dyn Error>> {
+        let mut bind_group_layouts = vec![&self.camera_bind_group_layout, &self.atlas.bind_group_layout];
+        if material.uniforms.is_some() {
+            bind_group_layouts.push(&self.material_bind_group_layout);
+        }
+        Self::register_pipeline(&self.device, self.config.format, self.sample_count, &bind_group_layouts, &self.ui_shader(), Some(material), &mut self.pipelines)
+    }
+
+    /// The ui shader pipelines are built from, the file in the source tree
+    /// while hot reloading and the one compiled into the binary otherwise.
+    fn ui_shader(&self) -> ShaderCode {
+        match self.shader_watcher {
+            Some(_) => ShaderCode::File(UI_SHADER_PATH.into()),
+            None => ShaderCode::Embedded(UI_SHADER),
+        }
+    }
+
+    /// Rebuilds the default pipeline and every material, returning the errors of any
+    /// that failed. Pipelines that fail keep their previous version.
+    fn rebuild_pipelines(&mut self) -> Vec<Box<dyn Error>> {
+        let default_result = Self::register_pipeline(
+            &self.device,
+            self.config.format,
+            self.sample_count,
+            &[&self.camera_bind_group_layout, &self.atlas.bind_group_layout],
+            &self.ui_shader(),
+            None,
+            &mut self.pipelines,
+        );
+        // Materials are compiled together with the ui shader so they're rebuilt too
+        let materials = self.materials.clone();
+        std::iter::once(default_result)
+            .chain(materials.iter().map(|material| self.register_material_pipeline(material)))
+            .filter_map(Result::err)
+            .collect()
+    }
+
+    /// The fixed camera the interface is drawn with, canvases have their own
+    /// added with [Interface::add_camera].
+    pub fn camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Sets how many samples per pixel are used for anti-aliasing, 1 turns MSAA off.
+    /// Fails if the adapter doesn't support `sample_count` for the frame and depth
+    /// formats, or a pipeline can't be rebuilt, in which case nothing changes.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<(), Box<dyn Error>> {
+        if sample_count == self.sample_count {
+            return Ok(());
+        }
+
+        if !self.supports_sample_count(self.config.format, sample_count) {
+            return Err(format!("{sample_count}x MSAA isn't supported for {:?}", self.config.format).into());
+        }
+
+        let previous_pipelines = self.pipelines.clone();
+        let previous_sample_count = self.sample_count;
+        self.sample_count = sample_count;
+        if let Some(error) = self.rebuild_pipelines().into_iter().next() {
+            self.pipelines = previous_pipelines;
+            self.sample_count = previous_sample_count;
+            return Err(error);
+        }
+
+        self.msaa_view = Self::create_msaa_view(&self.device, &self.config, sample_count);
+        self.depth_view = Self::create_depth_view(&self.device, &self.config, sample_count);
+
+        // The text brush has its own pipeline that has to match
+        let mut interface_guard = self.interface_arc.lock().unwrap();
+        interface_guard.set_text_target(sample_count, &self.device, &self.config, &self.queue, [self.window_size.width, self.window_size.height]);
+
+        Ok(())
+    }
+
+    /// Whether frames of `format` and the depth buffer can be drawn with `sample_count` samples.
+    fn supports_sample_count(&self, format: wgpu::TextureFormat, sample_count: u32) -> bool {
+        let supports = |format: wgpu::TextureFormat| {
+            let features = self.adapter.get_texture_format_features(format);
+            let adapter_specific = self.device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+            // WebGPU guarantees 1 and 4 samples, other counts need the adapter feature
+            let allowed = sample_count == 1 || sample_count == 4 || adapter_specific;
+            allowed && features.flags.sample_count_supported(sample_count)
+        };
+        let resolvable = sample_count == 1 || self.adapter.get_texture_format_features(format)
+            .flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE);
+        supports(format) && supports(DEPTH_FORMAT) && resolvable
+    }
+
+    /// Name, backend and type of the adapter frames are rendered with.
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    pub fn render_config(&self) -> &RenderConfig {
+        &self.render_config
+    }
+
+    /// Renegotiates the surface with `render_config` and reconfigures it, rebuilding
+    /// the pipelines when the format changes. Fails if the new format can't be drawn
+    /// with the current sample count or a pipeline can't be rebuilt, in which case
+    /// nothing changes.
+    pub fn set_render_config(&mut self, render_config: RenderConfig) -> Result<(), Box<dyn Error>> {
+        let mut config = self.config.clone();
+        render_config.negotiate(&self.target_capabilities(), &mut config);
+        if !self.supports_sample_count(config.format, self.sample_count) {
+            return Err(format!("{}x MSAA isn't supported for {:?}", self.sample_count, config.format).into());
+        }
+
+        let previous_config = std::mem::replace(&mut self.config, config);
+        if self.config.format != previous_config.format {
+            let previous_pipelines = self.pipelines.clone();
+            if let Some(error) = self.rebuild_pipelines().into_iter().next() {
+                self.pipelines = previous_pipelines;
+                self.config = previous_config;
+                return Err(error);
+            }
+        }
+        self.render_config = render_config;
+
+        match &mut self.target {
+            RenderTarget::Surface(surface) => surface.configure(&self.device, &self.config),
+            RenderTarget::Offscreen(texture) => *texture = Self::create_offscreen_texture(&self.device, &self.config),
+        }
+        if self.config.format != previous_config.format {
+            self.msaa_view = Self::create_msaa_view(&self.device, &self.config, self.sample_count);
+            let mut interface_guard = self.interface_arc.lock().unwrap();
+            interface_guard.set_text_target(self.sample_count, &self.device, &self.config, &self.queue, [self.window_size.width, self.window_size.height]);
+        }
+
+        Ok(())
+    }
+
+    /// What the render target can be configured with. Offscreen textures can be
+    /// any of the 8-bit RGBA formats frames can be read back from.
+    fn target_capabilities(&self) -> wgpu::SurfaceCapabilities {
+        match &self.target {
+            RenderTarget::Surface(surface) => surface.get_capabilities(&self.adapter),
+            RenderTarget::Offscreen(_) => wgpu::SurfaceCapabilities {
+                formats: vec![
+                    wgpu::TextureFormat::Rgba8UnormSrgb,
+                    wgpu::TextureFormat::Rgba8Unorm,
+                    wgpu::TextureFormat::Bgra8UnormSrgb,
+                    wgpu::TextureFormat::Bgra8Unorm,
+                ],
+                present_modes: vec![wgpu::PresentMode::Fifo],
+                alpha_modes: vec![wgpu::CompositeAlphaMode::Opaque],
+                usages: self.config.usage,
+            },
+        }
+    }
+
+    /// Compiles `material` so elements naming it are drawn with its fragment shader,
+    /// replacing any material registered under the same name. If the shader doesn't
+    /// compile an error is returned and the previous material, if any, is kept.
+    pub fn register_material(&mut self, material: Material) -> Result<(), Box<dyn Error>> {
+        if material.name == DEFAULT_PIPELINE || material.name == TRANSLUCENT_PIPELINE {
+            return Err(format!("'{}' is reserved for the default pipeline", material.name).into());
+        }
+        // Translucent materials get a second pipeline, its name can't be taken by another material
+        let clashes = |registered: &Material| {
+            translucent_pipeline_name(Some(&registered.name)) == material.name
+                || (material.translucent && registered.name == translucent_pipeline_name(Some(&material.name)))
+        };
+        if let Some(registered) = self.materials.iter().find(|registered| clashes(registered)) {
+            return Err(format!("Materials '{}' and '{}' would share a pipeline", material.name, registered.name).into());
+        }
+        self.register_material_pipeline(&material)?;
+        self.interface_arc.lock().unwrap().set_material_translucency(&material.name, material.translucent);
+
+        let bind_group = material.uniforms.as_ref().map(|uniforms| {
+            // Uniform structs are padded to 16 bytes in WGSL
+            let mut contents = uniforms.clone();
+            contents.resize(uniforms.len().next_multiple_of(16).max(16), 0);
+            let buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Material Uniform Buffer"),
+                contents: &contents,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+            });
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Material Bind Group"),
+                layout: &self.material_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }
+                ]
+            });
+            self.material_uniforms.insert(material.name.clone(), buffer);
+            bind_group
+        });
+        if bind_group.is_none() {
+            self.material_uniforms.remove(&material.name);
+        }
+        self.pipelines.set_bind_group(&material.name, bind_group);
+
+        self.materials.retain(|registered| registered.name != material.name);
+        self.materials.push(material);
+        Ok(())
+    }
+
+    /// Overwrites the start of a material's uniform buffer with `uniforms`,
+    /// this is how time or other animated values are passed to a material.
+    pub fn set_material_uniforms(&self, name: &str, uniforms: &[u8]) -> Result<(), Box<dyn Error>> {
+        let buffer = self.material_uniforms.get(name)
+            .ok_or_else(|| format!("No material called '{name}' with uniforms was registered"))?;
+        if uniforms.len() as u64 > buffer.size() {
+            return Err(format!("Material '{name}' has {} bytes of uniforms, got {}", buffer.size(), uniforms.len()).into());
+        }
+        self.queue.write_buffer(buffer, 0, uniforms);
+        Ok(())
+    }
+
+    /// Starts watching the shader files so [RenderState::reload_changed_shaders]
+    /// can pick up edits without restarting. Pipelines are otherwise built from
+    /// the shader compiled into the binary, this reads it from the source tree
+    /// instead, so it's only meant for development builds.
+    pub fn enable_shader_hot_reload(&mut self) {
+        let mut watcher = ShaderWatcher::new();
+        watcher.watch(UI_SHADER_PATH);
+        self.shader_watcher = Some(watcher);
+    }
+
+    /// Rebuilds the pipelines whose shader files changed since the last call,
+    /// does nothing unless [RenderState::enable_shader_hot_reload] was called.
+    /// Shaders that fail to compile are logged and the last working pipeline is kept.
+    pub fn reload_changed_shaders(&mut self) {
+        let Some(watcher) = self.shader_watcher.as_mut() else {
+            return;
+        };
+        if watcher.poll().is_empty() {
+            return;
+        }
+
+        let errors = self.rebuild_pipelines();
+        for error in &errors {
+            log::error!("{error}, keeping the last working pipeline");
+        }
+        if errors.is_empty() {
+            log::info!("Reloaded {UI_SHADER_PATH}");
+        }
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.window_size = PhysicalSize::new(width, height);
             self.config.width = width;
             self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+            match &mut self.target {
+                RenderTarget::Surface(surface) => surface.configure(&self.device, &self.config),
+                RenderTarget::Offscreen(texture) => *texture = Self::create_offscreen_texture(&self.device, &self.config),
+            }
+            self.msaa_view = Self::create_msaa_view(&self.device, &self.config, self.sample_count);
+            self.depth_view = Self::create_depth_view(&self.device, &self.config, self.sample_count);
             self.surface_configured = true;
 
             self.camera.update_screen_size(PhysicalSize::new(width, height));
@@ -247,44 +635,170 @@ impl RenderState {
         }
     }
 
-    pub fn render(&self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// Uploads `image` into the atlas under `name` so elements can use it as
+    /// their texture. When the atlas has no room left it is grown, and once it
+    /// reaches the device's size limit a new page is added instead.
+    pub fn add_texture(&mut self, name: &str, image: &image::RgbaImage) -> Result<AtlasHandle, Box<dyn Error>> {
+        let mut interface_guard = self.interface_arc.lock().unwrap();
+        if interface_guard.atlas().get_entry_by_name(name.to_string()).is_some() {
+            return Err(format!("The atlas already has a texture named '{name}'").into());
+        }
+
+        let (handle, region) = self.atlas.add(&self.device, &self.queue, name, image)?;
+
+        let [width, height] = self.atlas.size();
+        let mut ui_atlas = interface_guard.atlas().clone();
+        ui_atlas.resize(width, height, self.atlas.pages());
+        ui_atlas.add_entry(UiAtlasTexture::new(name.to_string(), region.x, region.y, region.width, region.height).with_page(region.page));
+        interface_guard.refresh_atlas(ui_atlas, &self.device, &self.queue, [self.window_size.width, self.window_size.height]);
+
+        Ok(handle)
+    }
+
+    /// Frees a texture added with [RenderState::add_texture], elements
+    /// still using it must be removed before the interface is rebuilt.
+    pub fn remove_texture(&mut self, handle: AtlasHandle) -> bool {
+        let Some(name) = self.atlas.remove(handle) else {
+            return false;
+        };
 
+        let mut interface_guard = self.interface_arc.lock().unwrap();
+        let mut ui_atlas = interface_guard.atlas().clone();
+        ui_atlas.remove_entry(&name);
+        interface_guard.refresh_atlas(ui_atlas, &self.device, &self.queue, [self.window_size.width, self.window_size.height]);
+
+        true
+    }
+
+    pub fn render(&self) -> Result<(), wgpu::SurfaceError> {
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render encoder")
         });
 
-        let interface_guard = self.interface_arc.lock().unwrap();
+        match &self.target {
+            RenderTarget::Surface(surface) => {
+                let output = surface.get_current_texture()?;
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor { 
-                label: Some("Render pass"), 
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::from_hex("#21262d")),
-                        store: wgpu::StoreOp::Store
-                    },
-                    depth_slice: None
-                })], 
-                depth_stencil_attachment: None, 
-                timestamp_writes: None, 
-                occlusion_query_set: None 
-            });
-
-            render_pass.set_pipeline(&self.ui_pipeline);
-            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-            render_pass.set_bind_group(1, &self.texture_bind_group, &[]);
+                self.encode_frame(&mut encoder, &view);
+                self.queue.submit(std::iter::once(encoder.finish()));
+                output.present();
+            }
+            RenderTarget::Offscreen(texture) => {
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-            interface_guard.render(&mut render_pass);
-            interface_guard.draw_text_brush(&mut render_pass);
+                self.encode_frame(&mut encoder, &view);
+                self.queue.submit(std::iter::once(encoder.finish()));
+            }
         }
 
+        Ok(())
+    }
+
+    /// Renders a frame into the offscreen texture and copies it back
+    /// to the CPU as an RGBA image. Only a [RenderState] created through
+    /// [RenderState::new_headless] can be read back.
+    pub fn read_frame(&self) -> Result<image::RgbaImage, Box<dyn Error>> {
+        let RenderTarget::Offscreen(texture) = &self.target else {
+            return Err("Frames can only be read back from a headless RenderState".into());
+        };
+
+        let width = self.config.width;
+        let height = self.config.height;
+
+        // Rows copied out of a texture must be padded to COPY_BYTES_PER_ROW_ALIGNMENT
+        let unpadded_bytes_per_row = 4 * width;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback encoder")
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.encode_frame(&mut encoder, &view);
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
 
+        let buffer_slice = readback_buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait)?;
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let padded_data = buffer_slice.get_mapped_range();
+            for row in padded_data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        readback_buffer.unmap();
+
+        if matches!(self.config.format, wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        image::RgbaImage::from_raw(width, height, pixels).ok_or_else(|| "Frame readback had an unexpected size".into())
+    }
+
+    /// Renders a frame with [RenderState::read_frame] and writes it to `path` as a PNG.
+    pub fn save_frame(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        self.read_frame()?.save_with_format(path, image::ImageFormat::Png)?;
         Ok(())
     }
+
+    fn encode_frame(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut interface_guard = self.interface_arc.lock().unwrap();
+        interface_guard.update_cameras(&self.device, &self.queue, &self.camera_bind_group_layout, [self.window_size.width, self.window_size.height]);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor { 
+            label: Some("Render pass"), 
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.msaa_view.as_ref().unwrap_or(view),
+                resolve_target: self.msaa_view.as_ref().map(|_| view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::from_hex("#21262d")),
+                    // Only the resolved frame is needed once the pass ends
+                    store: if self.msaa_view.is_some() { wgpu::StoreOp::Discard } else { wgpu::StoreOp::Store }
+                },
+                depth_slice: None
+            })], 
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None, 
+            occlusion_query_set: None 
+        });
+
+        interface_guard.render(&mut render_pass, &self.pipelines, &self.camera_bind_group, &self.atlas.bind_group, [self.config.width, self.config.height]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,50 @@
+/// A custom fragment shader elements can be drawn with instead of the default one,
+/// registered with [RenderState::register_material](crate::RenderState::register_material).
+///
+/// The fragment source is compiled together with `ui_shader.wgsl`, so it can use
+/// `VertexOutput`, `texture` and `texture_sampler` from there. A material with
+/// uniforms gets them bound at `@group(2) @binding(0)`.
+///
+/// Materials write depth like opaque quads unless they're marked translucent
+/// with [Material::with_translucency], which is needed when the fragment shader
+/// returns colors that aren't fully opaque.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub(crate) name: String,
+    pub(crate) fragment_source: String,
+    pub(crate) fragment_entry: String,
+    pub(crate) uniforms: Option<Vec<u8>>,
+    pub(crate) translucent: bool,
+}
+
+impl Material {
+    /// `fragment_source` must define a `@fragment` function called `fragment_entry`,
+    /// which can't be `fs_main` since the default shader already uses it.
+    pub fn new(name: &str, fragment_source: &str, fragment_entry: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            fragment_source: fragment_source.to_string(),
+            fragment_entry: fragment_entry.to_string(),
+            uniforms: None,
+            translucent: false,
+        }
+    }
+
+    /// Sets the initial contents of the material's uniform buffer, they can be
+    /// changed later with [RenderState::set_material_uniforms](crate::RenderState::set_material_uniforms).
+    pub fn with_uniforms(mut self, uniforms: &[u8]) -> Self {
+        self.uniforms = Some(uniforms.to_vec());
+        self
+    }
+
+    /// Draws the material without writing depth, so elements and text under
+    /// it show through wherever its fragment shader returns translucent colors.
+    pub fn with_translucency(mut self) -> Self {
+        self.translucent = true;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -1,14 +1,62 @@
-use std::{env::current_dir, fs};
+use std::{collections::HashMap, error::Error, fs, path::{Path, PathBuf}, time::SystemTime};
 
 use wgpu::{
     BindGroupLayout, BlendState, ColorTargetState, ColorWrites, DepthBiasState, DepthStencilState, Device, Face, FragmentState, FrontFace, MultisampleState, PipelineCompilationOptions, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderSource, StencilState, TextureFormat, VertexBufferLayout, VertexState
 };
 
+/// Format of the depth buffer the interface is drawn with.
+pub(crate) const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Name the default ui pipeline is registered under.
+pub(crate) const DEFAULT_PIPELINE: &str = "ui";
+
+/// Name of the default ui pipeline without depth writes, shadows and
+/// translucent quads are drawn with it so what's under them still shows.
+pub(crate) const TRANSLUCENT_PIPELINE: &str = "ui_translucent";
+
+/// Name of the pipeline translucent instances of `material` are drawn with,
+/// the [TRANSLUCENT_PIPELINE] for instances without a material.
+pub(crate) fn translucent_pipeline_name(material: Option<&str>) -> String {
+    match material {
+        Some(name) => format!("{name}_translucent"),
+        None => TRANSLUCENT_PIPELINE.to_string(),
+    }
+}
+
+/// Where a pipeline's WGSL comes from.
+#[derive(Debug, Clone)]
+pub(crate) enum ShaderCode {
+    /// Compiled into the binary.
+    Embedded(&'static str),
+    /// Read from disk whenever the pipeline is built, used for hot reloading.
+    File(PathBuf),
+}
+
+impl ShaderCode {
+    fn read(&self) -> Result<String, Box<dyn Error>> {
+        match self {
+            ShaderCode::Embedded(source) => Ok(source.to_string()),
+            ShaderCode::File(path) => fs::read_to_string(path)
+                .map_err(|e| format!("Can't read shader {}: {e}", path.display()).into()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            ShaderCode::Embedded(_) => "the embedded shader".to_string(),
+            ShaderCode::File(path) => path.display().to_string(),
+        }
+    }
+}
+
 pub(crate) struct PipeLineBuilder<'a> {
-    shader_filename: String,
+    shader: ShaderCode,
+    appended_source: String,
     vertex_entry: String,
     fragment_entry: String,
     pixel_format: wgpu::TextureFormat,
+    sample_count: u32,
+    depth_write_enabled: bool,
     vertex_buffer_layouts: Vec<VertexBufferLayout<'static>>,
     bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
     device: &'a Device,
@@ -17,13 +65,16 @@ pub(crate) struct PipeLineBuilder<'a> {
 impl <'a> PipeLineBuilder <'a> {
     pub(crate) fn new(device: &'a Device) -> Self {
         PipeLineBuilder {
-            shader_filename: "empty".to_string(),
+            shader: ShaderCode::Embedded(""),
+            appended_source: String::new(),
             vertex_entry: "empty".to_string(),
             fragment_entry: "empty".to_string(),
             pixel_format: TextureFormat::Rgba8Unorm,
+            sample_count: 1,
+            depth_write_enabled: true,
             vertex_buffer_layouts: Vec::new(),
             bind_group_layouts: Vec::new(),
-            device: device,
+            device,
         }
     }
 
@@ -31,14 +82,22 @@ impl <'a> PipeLineBuilder <'a> {
         self.vertex_buffer_layouts.clear();
     }
 
-    pub(crate) fn set_shader_module(&mut self, shader_filename: &str, vertex_entry: &str, fragment_entry: &str) -> &mut Self {
-        self.shader_filename = shader_filename.to_string();
+    pub(crate) fn set_shader_module(&mut self, shader: ShaderCode, vertex_entry: &str, fragment_entry: &str) -> &mut Self {
+        self.shader = shader;
         self.vertex_entry = vertex_entry.to_string();
         self.fragment_entry = fragment_entry.to_string();
 
         self
     }
 
+    /// Adds WGSL to the end of the shader before it is compiled,
+    /// used by materials to define their own fragment entry point.
+    pub(crate) fn append_shader_source(&mut self, source: &str) -> &mut Self {
+        self.appended_source.push('\n');
+        self.appended_source.push_str(source);
+        self
+    }
+
     pub(crate) fn add_vertex_buffer_layout(&mut self, layout: VertexBufferLayout<'static>) -> &mut Self {
         self.vertex_buffer_layouts.push(layout);
         self
@@ -54,7 +113,21 @@ impl <'a> PipeLineBuilder <'a> {
         self
     }
 
-    pub(crate) fn build(&mut self, label: &str) -> RenderPipeline {
+    /// Sets how many samples per pixel the color and depth targets have.
+    pub(crate) fn set_sample_count(&mut self, sample_count: u32) -> &mut Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Sets whether fragments write their depth, so later fragments behind them are hidden.
+    pub(crate) fn set_depth_write(&mut self, depth_write_enabled: bool) -> &mut Self {
+        self.depth_write_enabled = depth_write_enabled;
+        self
+    }
+
+    /// Compiles the shader and creates the pipeline, failing if the shader
+    /// can't be read or doesn't compile.
+    pub(crate) fn build(&mut self, label: &str) -> Result<RenderPipeline, Box<dyn Error>> {
 
         let pipeline_layout_descriptor = PipelineLayoutDescriptor {
             label: Some("Pipeline Layout"),
@@ -62,10 +135,11 @@ impl <'a> PipeLineBuilder <'a> {
             push_constant_ranges: &[],
         };
 
-        let mut filepath = current_dir().unwrap();
-        filepath.push(self.shader_filename.as_str());
-        let filepath = filepath.into_os_string().into_string().unwrap();
-        let source_code = fs::read_to_string(filepath).expect("Can't read source code!");
+        let mut source_code = self.shader.read()?;
+        source_code.push_str(&self.appended_source);
+
+        // Compile errors are caught here rather than going to the device's error handler
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
 
         let shader_module_descriptor = ShaderModuleDescriptor {
             label: Some("Shader Module"),
@@ -82,9 +156,9 @@ impl <'a> PipeLineBuilder <'a> {
             write_mask: ColorWrites::ALL,
         })];
 
-        let _depth_stencil = DepthStencilState {
-            format: TextureFormat::Depth32Float,
-            depth_write_enabled: true,
+        let depth_stencil = DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: self.depth_write_enabled,
             depth_compare: wgpu::CompareFunction::Less,
             stencil: StencilState::default(),
             bias: DepthBiasState::default(),
@@ -117,9 +191,9 @@ impl <'a> PipeLineBuilder <'a> {
                 polygon_mode: PolygonMode::Fill,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil: Some(depth_stencil),
             multisample: MultisampleState {
-                count: 1,
+                count: self.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -131,6 +205,158 @@ impl <'a> PipeLineBuilder <'a> {
 
         self.reset();
 
-        pipeline
+        if let Some(error) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(format!("{label} failed to build from {}: {error}", self.shader.describe()).into());
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Builds the pipeline and adds it to `registry` under `name`,
+    /// replacing any pipeline already registered with that name.
+    pub(crate) fn register(&mut self, name: &str, registry: &mut PipelineRegistry) -> Result<(), Box<dyn Error>> {
+        let pipeline = self.build(name)?;
+        registry.insert(name, pipeline);
+        Ok(())
+    }
+}
+
+/// A pipeline along with the bind group its material's uniforms live in.
+#[derive(Clone)]
+pub(crate) struct RegisteredPipeline {
+    pub(crate) pipeline: RenderPipeline,
+    pub(crate) bind_group: Option<wgpu::BindGroup>,
+}
+
+/// Render pipelines looked up by name, elements without a
+/// material are drawn with the [DEFAULT_PIPELINE].
+#[derive(Clone)]
+pub(crate) struct PipelineRegistry {
+    pipelines: HashMap<String, RegisteredPipeline>,
+}
+
+impl PipelineRegistry {
+    pub(crate) fn new() -> Self {
+        Self { pipelines: HashMap::new() }
     }
-}
\ No newline at end of file
+
+    /// Adds or replaces a pipeline, a replaced pipeline keeps its bind group.
+    pub(crate) fn insert(&mut self, name: &str, pipeline: RenderPipeline) {
+        match self.pipelines.get_mut(name) {
+            Some(registered) => registered.pipeline = pipeline,
+            None => {
+                self.pipelines.insert(name.to_string(), RegisteredPipeline { pipeline, bind_group: None });
+            }
+        }
+    }
+
+    /// Sets the bind group of the pipeline registered as `name` and of its translucent variant.
+    pub(crate) fn set_bind_group(&mut self, name: &str, bind_group: Option<wgpu::BindGroup>) {
+        if let Some(registered) = self.pipelines.get_mut(&translucent_pipeline_name(Some(name))) {
+            registered.bind_group = bind_group.clone();
+        }
+        if let Some(registered) = self.pipelines.get_mut(name) {
+            registered.bind_group = bind_group;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        self.pipelines.remove(name);
+    }
+
+    /// Returns the pipeline registered as `name`, falling
+    /// back to the default one if there is none.
+    pub(crate) fn get_or_default(&self, name: Option<&str>) -> &RegisteredPipeline {
+        name.and_then(|name| self.pipelines.get(name))
+            .or_else(|| self.pipelines.get(DEFAULT_PIPELINE))
+            .expect("The default pipeline was never registered")
+    }
+
+    /// Returns the pipeline translucent instances of `material` are drawn with,
+    /// falling back like [PipelineRegistry::get_or_default] if it has no translucent variant.
+    pub(crate) fn get_translucent_or_default(&self, material: Option<&str>) -> &RegisteredPipeline {
+        self.pipelines.get(&translucent_pipeline_name(material))
+            .unwrap_or_else(|| self.get_or_default(material))
+    }
+}
+
+/// Keeps track of when shader files were last modified so their
+/// pipelines can be rebuilt while the app is running.
+pub(crate) struct ShaderWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl ShaderWatcher {
+    pub(crate) fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    pub(crate) fn watch(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        let modified = Self::modified(&path);
+        self.files.push((path, modified));
+    }
+
+    /// Returns the watched files that changed since the last poll.
+    pub(crate) fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last_modified) in &mut self.files {
+            let modified = Self::modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::{Arc, Mutex}, time::{Duration, SystemTime}};
+
+    use image::RgbaImage;
+    use winit::dpi::PhysicalSize;
+
+    use crate::{definitions::{InstanceRaw, UiAtlas, Vertex}, user_interface::interface::Interface, RenderState, UI_SHADER_PATH};
+
+    use super::{PipeLineBuilder, ShaderCode, ShaderWatcher};
+
+    #[test]
+    fn edited_shaders_are_detected_and_rebuilt() {
+        #[allow(clippy::arc_with_non_send_sync)]
+        let interface = Arc::new(Mutex::new(Interface::new(UiAtlas::from_image(RgbaImage::new(1, 1)))));
+        let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(8, 8), interface)).unwrap();
+
+        let shader_path = std::env::temp_dir().join(format!("ui_shader_{}.wgsl", std::process::id()));
+        fs::copy(UI_SHADER_PATH, &shader_path).unwrap();
+        let build = || PipeLineBuilder::new(&render_state.device)
+            .set_pixel_format(render_state.config.format)
+            .set_shader_module(ShaderCode::File(shader_path.clone()), "vs_main", "fs_main")
+            .add_vertex_buffer_layout(Vertex::description())
+            .add_vertex_buffer_layout(InstanceRaw::desc())
+            .add_bind_group_layout(&render_state.camera_bind_group_layout)
+            .add_bind_group_layout(&render_state.atlas.bind_group_layout)
+            .build("Test Pipeline");
+
+        let mut watcher = ShaderWatcher::new();
+        watcher.watch(&shader_path);
+        assert!(watcher.poll().is_empty());
+        assert!(build().is_ok());
+
+        fs::write(&shader_path, "@vertex fn vs_main( {").unwrap();
+        // Writes within the file system's timestamp resolution could look unchanged
+        fs::File::options().write(true).open(&shader_path).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(1)).unwrap();
+        assert_eq!(watcher.poll(), std::slice::from_ref(&shader_path));
+        assert!(watcher.poll().is_empty());
+        assert!(build().is_err());
+
+        fs::remove_file(&shader_path).unwrap();
+        assert!(build().is_err());
+    }
+}
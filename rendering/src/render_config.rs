@@ -0,0 +1,101 @@
+/// How frames are presented to the window, passed to [RenderState::new](crate::RenderState::new)
+/// and changed with [RenderState::set_render_config](crate::RenderState::set_render_config).
+///
+/// Every setting is a preference, anything the surface doesn't support falls back
+/// to something it does. The negotiated values end up in [RenderState::config](crate::RenderState::config).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderConfig {
+    pub(crate) present_mode: wgpu::PresentMode,
+    pub(crate) frame_latency: u32,
+    pub(crate) format: Option<wgpu::TextureFormat>,
+    pub(crate) alpha_mode: Option<wgpu::CompositeAlphaMode>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            present_mode: wgpu::PresentMode::Fifo,
+            frame_latency: 2,
+            format: None,
+            alpha_mode: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the preferred present mode, `Fifo` (vsync) is used when the surface
+    /// doesn't support it since every surface does.
+    pub fn with_present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    /// Sets how many frames may be queued ahead of the one on screen, lower
+    /// values reduce input latency at the cost of throughput.
+    pub fn with_frame_latency(mut self, frame_latency: u32) -> Self {
+        self.frame_latency = frame_latency;
+        self
+    }
+
+    /// Sets the preferred surface format, otherwise the first sRGB format the
+    /// surface supports is used.
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets the preferred alpha mode, otherwise the surface's first one is used.
+    pub fn with_alpha_mode(mut self, alpha_mode: wgpu::CompositeAlphaMode) -> Self {
+        self.alpha_mode = Some(alpha_mode);
+        self
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.present_mode
+    }
+
+    pub fn frame_latency(&self) -> u32 {
+        self.frame_latency
+    }
+
+    pub fn format(&self) -> Option<wgpu::TextureFormat> {
+        self.format
+    }
+
+    pub fn alpha_mode(&self) -> Option<wgpu::CompositeAlphaMode> {
+        self.alpha_mode
+    }
+
+    /// Picks the supported values closest to the preferred ones and writes them into `config`.
+    pub(crate) fn negotiate(&self, capabilities: &wgpu::SurfaceCapabilities, config: &mut wgpu::SurfaceConfiguration) {
+        config.format = self.format
+            .filter(|format| capabilities.formats.contains(format))
+            .or_else(|| capabilities.formats.iter().copied().find(|format| format.is_srgb()))
+            .unwrap_or(capabilities.formats[0]);
+        if let Some(format) = self.format && format != config.format {
+            log::warn!("Surface format {format:?} isn't supported, using {:?}", config.format);
+        }
+
+        // The automatic modes are resolved by wgpu so they're never listed as supported
+        let automatic = matches!(self.present_mode, wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync);
+        config.present_mode = if automatic || capabilities.present_modes.contains(&self.present_mode) {
+            self.present_mode
+        } else {
+            log::warn!("Present mode {:?} isn't supported, using Fifo", self.present_mode);
+            wgpu::PresentMode::Fifo
+        };
+
+        config.alpha_mode = self.alpha_mode
+            .filter(|alpha_mode| capabilities.alpha_modes.contains(alpha_mode))
+            .unwrap_or(capabilities.alpha_modes[0]);
+        if let Some(alpha_mode) = self.alpha_mode && alpha_mode != config.alpha_mode {
+            log::warn!("Alpha mode {alpha_mode:?} isn't supported, using {:?}", config.alpha_mode);
+        }
+
+        config.desired_maximum_frame_latency = self.frame_latency;
+    }
+}
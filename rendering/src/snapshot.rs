@@ -0,0 +1,159 @@
+use std::{env, error::Error, fs, path::PathBuf, sync::{Arc, Mutex}};
+
+use image::{Rgba, RgbaImage};
+use winit::dpi::PhysicalSize;
+
+use crate::{definitions::UiAtlas, user_interface::{interface::Interface, UserInterface}, RenderState};
+
+/// Golden-image test for an [Interface] layout.
+///
+/// The interface is built with [Interface::show], rendered headlessly at a
+/// fixed window size and compared against `<snapshot_dir>/<name>.png`.
+/// When the reference is missing, or the `UPDATE_SNAPSHOTS` environment
+/// variable is set, the rendered frame is written as the new reference.
+/// Only built with the `snapshot` feature, which the crate's own tests enable.
+pub struct SnapshotTest {
+    name: String,
+    window_size: [u32; 2],
+    atlas: UiAtlas,
+    tolerance: u8,
+    max_mismatched_pixels: usize,
+    sample_count: u32,
+    scale_factor: f32,
+    snapshot_dir: PathBuf,
+}
+
+/// The result of comparing a rendered frame against its reference.
+pub struct SnapshotComparison {
+    pub mismatched_pixels: usize,
+    /// Mismatched pixels are drawn in red over a dimmed copy of the reference.
+    pub diff: RgbaImage,
+}
+
+impl SnapshotTest {
+    pub fn new(name: &str, window_size: [u32; 2], atlas: UiAtlas) -> Self {
+        Self {
+            name: name.to_string(),
+            window_size,
+            atlas,
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+            sample_count: 1,
+            scale_factor: 1.0,
+            snapshot_dir: PathBuf::from("tests/snapshots"),
+        }
+    }
+
+    /// Sets the largest per-channel difference that still counts as a match.
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets how many pixels may differ before the snapshot fails.
+    pub fn with_max_mismatched_pixels(mut self, max_mismatched_pixels: usize) -> Self {
+        self.max_mismatched_pixels = max_mismatched_pixels;
+        self
+    }
+
+    /// Sets how many samples per pixel the frame is rendered with.
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Renders the frame as if on a display with `scale_factor`, see [Interface::set_scale_factor].
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Sets the directory the reference, actual and diff images are stored in,
+    /// relative paths are resolved from the current directory.
+    pub fn with_snapshot_dir(mut self, snapshot_dir: impl Into<PathBuf>) -> Self {
+        self.snapshot_dir = snapshot_dir.into();
+        self
+    }
+
+    /// Builds an [Interface] with `elements_builder` and renders a single frame of it.
+    pub fn render(&self, elements_builder: impl FnOnce(&mut UserInterface)) -> Result<RgbaImage, Box<dyn Error>> {
+        let mut interface = Interface::new(self.atlas.clone());
+        interface.show(elements_builder);
+        interface.set_scale_factor(self.scale_factor);
+
+        #[allow(clippy::arc_with_non_send_sync)]
+        let interface_arc = Arc::new(Mutex::new(interface));
+        let window_size = PhysicalSize::new(self.window_size[0], self.window_size[1]);
+        let mut render_state = pollster::block_on(RenderState::new_headless(window_size, interface_arc.clone()))?;
+        render_state.set_sample_count(self.sample_count)?;
+
+        {
+            let mut interface_guard = interface_arc.lock().unwrap();
+            interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+            interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, self.window_size);
+        }
+
+        render_state.read_frame()
+    }
+
+    /// Renders the layout and panics if it does not match the stored reference.
+    /// On failure `<name>.actual.png` and `<name>.diff.png` are written next to it.
+    pub fn assert_matches(&self, elements_builder: impl FnOnce(&mut UserInterface)) {
+        let actual = self.render(elements_builder)
+            .unwrap_or_else(|e| panic!("Snapshot '{}' could not be rendered: {e}", self.name));
+
+        fs::create_dir_all(&self.snapshot_dir).expect("Can't create snapshot directory!");
+        let reference_path = self.path("png");
+
+        if env::var_os("UPDATE_SNAPSHOTS").is_some() || !reference_path.exists() {
+            actual.save(&reference_path).expect("Can't write reference snapshot!");
+            return;
+        }
+
+        let reference = image::open(&reference_path).expect("Can't read reference snapshot!").to_rgba8();
+        if reference.dimensions() != actual.dimensions() {
+            actual.save(self.path("actual.png")).expect("Can't write actual snapshot!");
+            panic!(
+                "Snapshot '{}' is {:?} but the reference is {:?}",
+                self.name, actual.dimensions(), reference.dimensions()
+            );
+        }
+
+        let comparison = compare_images(&actual, &reference, self.tolerance);
+        if comparison.mismatched_pixels > self.max_mismatched_pixels {
+            actual.save(self.path("actual.png")).expect("Can't write actual snapshot!");
+            comparison.diff.save(self.path("diff.png")).expect("Can't write diff snapshot!");
+            panic!(
+                "Snapshot '{}' has {} mismatched pixels (allowed {}), see {}",
+                self.name, comparison.mismatched_pixels, self.max_mismatched_pixels, self.path("diff.png").display()
+            );
+        }
+    }
+
+    fn path(&self, extension: &str) -> PathBuf {
+        self.snapshot_dir.join(format!("{}.{extension}", self.name))
+    }
+}
+
+/// Compares two images of the same size pixel by pixel, a pixel
+/// mismatches when any channel differs by more than `tolerance`.
+pub fn compare_images(actual: &RgbaImage, reference: &RgbaImage, tolerance: u8) -> SnapshotComparison {
+    let mut diff = RgbaImage::new(reference.width(), reference.height());
+    let mut mismatched_pixels = 0;
+
+    for ((actual_pixel, reference_pixel), diff_pixel) in actual.pixels().zip(reference.pixels()).zip(diff.pixels_mut()) {
+        let mismatched = actual_pixel.0.iter()
+            .zip(reference_pixel.0.iter())
+            .any(|(a, r)| a.abs_diff(*r) > tolerance);
+
+        if mismatched {
+            mismatched_pixels += 1;
+            *diff_pixel = Rgba([255, 0, 0, 255]);
+        } else {
+            let [r, g, b, _] = reference_pixel.0;
+            *diff_pixel = Rgba([r / 4, g / 4, b / 4, 255]);
+        }
+    }
+
+    SnapshotComparison { mismatched_pixels, diff }
+}
@@ -1,8 +1,8 @@
 use std::time::{Duration, SystemTime};
 
-use glam::f32;
+use glam::{f32, Mat2, Vec2};
 
-use crate::definitions::GeometryType;
+use crate::{camera::CameraHandle, definitions::{GeometryType, DEFAULT_TEXT_SIZE, MAX_GRADIENT_STOPS}};
 pub trait Element {
     /// Returns an elements id
     fn get_id(&self) -> u32;
@@ -36,6 +36,21 @@ pub trait Element {
     /// file stored in the assets file.
     fn get_texture_name(&self) -> Option<String>;
 
+    /// Returns the name of the material this element is drawn
+    /// with, None draws it with the default ui shader.
+    fn get_material(&self) -> Option<String>;
+
+    /// Returns the element's z-index, elements with a higher z-index are
+    /// drawn over and hit before those with a lower one. Elements with the
+    /// same z-index stack in the order they were added.
+    fn get_z_index(&self) -> i32;
+
+    /// Returns the rounded corners, border and shadow the element is drawn with.
+    fn get_style(&self) -> QuadStyle;
+
+    /// Returns the rotation and skew applied to the element around its pivot.
+    fn get_transform(&self) -> Transform;
+
     /// Returns an option, if called on a label element this
     /// would be the text to be rendered.
     fn get_text(&mut self) -> Option<&String>;
@@ -44,6 +59,10 @@ pub trait Element {
     /// whether the user's mouse is within an element.
     fn get_bounds(&self, window_size: [u32; 2]) -> Option<[f32; 2]>;
 
+    /// Returns the size of the element's text in the same pixels as its
+    /// position, None if it has no text.
+    fn get_text_size(&self) -> Option<f32>;
+
     /// Returns whether the element this was called on 
     /// has bounds within the input elements bounds.
     fn get_layer(&self, input: [f32; 4], window_size: [u32; 2]) -> bool;
@@ -52,6 +71,36 @@ pub trait Element {
     /// type of element the user is interacting with.
     fn get_element_type(&self) -> ElementType;
 
+    /// Returns the rectangle the element is clipped to in pixels as
+    /// `[left, top, right, bottom]`, None if it isn't clipped.
+    fn get_clip_rect(&self, window_size: [u32; 2]) -> Option<[f32; 4]>;
+
+    /// Returns the camera the element is drawn through, None for the fixed
+    /// camera. Positions of elements with a camera are in its world space.
+    fn get_camera(&self) -> Option<CameraHandle>;
+
+    /// Returns the rectangle the element covers relative to the window as
+    /// `[left, top, right, bottom]`, None if its size isn't relative to the
+    /// window. Children added inside the element are placed in this rectangle.
+    fn get_relative_rect(&self) -> Option<[f32; 4]>;
+
+    /// Returns the key the element is matched by when the interface is rebuilt,
+    /// see [Interface::reconcile](crate::user_interface::interface::Interface::reconcile).
+    fn get_key(&self) -> &str;
+
+    /// Returns the state the element picked up from input since it was built,
+    /// None if it has none.
+    fn get_state(&self) -> Option<WidgetState>;
+
+    /// Returns the alpha the element was highlighted with, which replaces its
+    /// color's alpha when it's drawn. None if it isn't highlighted.
+    fn get_highlight(&self) -> Option<f32>;
+
+    /// Whether the element changed since the interface last wrote its
+    /// instances. Setters that change how the element is drawn mark it dirty,
+    /// see [Interface::update_instances](crate::user_interface::interface::Interface::update_instances).
+    fn is_dirty(&self) -> bool;
+
 
 
 
@@ -65,6 +114,39 @@ pub trait Element {
 
     fn set_text(&mut self, text: &str, window_size: [u32; 2]);
 
+    /// Sets how many pixels a logical pixel covers, text sizes are given in
+    /// logical pixels. This is set by the interface, see [Interface::set_scale_factor](crate::user_interface::interface::Interface::set_scale_factor).
+    fn set_scale_factor(&mut self, scale_factor: f32);
+
+    /// Clips the element to a rectangle relative to the window, given as
+    /// `[left, top, right, bottom]`. This is set from the clip scope the
+    /// element was added in, see [UserInterface::clip](crate::user_interface::UserInterface::clip).
+    fn set_clip_rect(&mut self, relative_clip_rect: Option<[f32; 4]>);
+
+    /// Sets the camera the element is drawn through, this is set from the
+    /// canvas the element was added in, see [UserInterface::canvas](crate::user_interface::UserInterface::canvas).
+    fn set_camera(&mut self, camera: Option<CameraHandle>);
+
+    /// Places the element in its parent's rectangle, given relative to the window
+    /// as `[left, top, right, bottom]`. The position and scale it was built with
+    /// are relative to that rectangle. This is set by the interface, see
+    /// [UserInterface::parent](crate::user_interface::UserInterface::parent).
+    fn set_parent_rect(&mut self, relative_parent_rect: [f32; 4]);
+
+    /// Moves the element to cover `relative_rect`, given relative to the window
+    /// as `[left, top, right, bottom]`, elements sized in pixels are centered in
+    /// it. This is set by the layout container the element is in, see [UserInterface::row](crate::user_interface::UserInterface::row).
+    fn set_relative_rect(&mut self, relative_rect: [f32; 4]);
+
+    /// Sets the key the element is matched by, this is set by the interface
+    /// from the key scope the element was added in, see [UserInterface::keyed](crate::user_interface::UserInterface::keyed).
+    fn set_key(&mut self, key: String);
+
+    /// Restores state taken from the element this one replaces.
+    fn set_state(&mut self, state: WidgetState);
+
+    /// Called by the interface once the element's instances were written.
+    fn clear_dirty(&mut self);
 
 
 
@@ -82,26 +164,163 @@ pub trait Element {
     fn is_cursor_within_bounds(&self, cursor_position: [f32; 2], element_pos: [f32; 2], element_scale: [f32;2]) -> bool;
 }
 
+/// Where an element is within its parent, the position, scale and bounds it
+/// was built with are relative to the parent's rect.
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    position: [f32; 2],
+    scale: [f32; 2],
+    bounds: Option<[f32; 2]>,
+    /// The whole window for elements without a parent.
+    parent_rect: [f32; 4],
+}
+
+impl Placement {
+    fn new(position: [f32; 2], scale: [f32; 2]) -> Self {
+        Self {
+            position,
+            scale,
+            bounds: None,
+            parent_rect: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+
+    /// Returns the position relative to the window.
+    fn position(&self) -> [f32; 2] {
+        position_in_parent(self.position, self.parent_rect)
+    }
+
+    /// Returns the scale relative to the window.
+    fn scale(&self) -> [f32; 2] {
+        scale_in_parent(self.scale, self.parent_rect)
+    }
+
+    fn bounds(&self) -> Option<[f32; 2]> {
+        self.bounds.map(|bounds| scale_in_parent(bounds, self.parent_rect))
+    }
+
+    fn rect(&self) -> [f32; 4] {
+        rect_around(self.position(), self.scale())
+    }
+
+    /// Moves the parent rect the placement is relative to, returns whether it changed.
+    fn set_parent_rect(&mut self, parent_rect: [f32; 4]) -> bool {
+        let changed = self.parent_rect != parent_rect;
+        self.parent_rect = parent_rect;
+        changed
+    }
+
+    /// Moves the element to cover `rect`, given relative to the window.
+    /// Returns whether that moved or resized it.
+    fn set_rect(&mut self, rect: [f32; 4]) -> bool {
+        let previous = (self.position, self.scale);
+        let parent_size = [
+            (self.parent_rect[2] - self.parent_rect[0]).max(f32::EPSILON),
+            (self.parent_rect[3] - self.parent_rect[1]).max(f32::EPSILON),
+        ];
+        self.position = [
+            ((rect[0] + rect[2]) / 2.0 - self.parent_rect[0]) / parent_size[0],
+            ((rect[1] + rect[3]) / 2.0 - self.parent_rect[1]) / parent_size[1],
+        ];
+        self.scale = [(rect[2] - rect[0]) / parent_size[0], (rect[3] - rect[1]) / parent_size[1]];
+        previous != (self.position, self.scale)
+    }
+}
+
+/// Maps a position relative to `parent_rect` to one relative to the window.
+fn position_in_parent(relative_position: [f32; 2], parent_rect: [f32; 4]) -> [f32; 2] {
+    [
+        parent_rect[0] + relative_position[0] * (parent_rect[2] - parent_rect[0]),
+        parent_rect[1] + relative_position[1] * (parent_rect[3] - parent_rect[1]),
+    ]
+}
+
+/// Maps a size relative to `parent_rect` to one relative to the window.
+fn scale_in_parent(relative_scale: [f32; 2], parent_rect: [f32; 4]) -> [f32; 2] {
+    [
+        relative_scale[0] * (parent_rect[2] - parent_rect[0]),
+        relative_scale[1] * (parent_rect[3] - parent_rect[1]),
+    ]
+}
+
+/// The `[left, top, right, bottom]` rectangle around a center and scale.
+fn rect_around(relative_position: [f32; 2], relative_scale: [f32; 2]) -> [f32; 4] {
+    [
+        relative_position[0] - relative_scale[0] / 2.0,
+        relative_position[1] - relative_scale[1] / 2.0,
+        relative_position[0] + relative_scale[0] / 2.0,
+        relative_position[1] + relative_scale[1] / 2.0,
+    ]
+}
+
+/// Converts a `[left, top, right, bottom]` rectangle relative to the window to pixels.
+fn relative_rect_to_pixels(rect: [f32; 4], window_size: [u32; 2]) -> [f32; 4] {
+    [
+        rect[0] * window_size[0] as f32,
+        rect[1] * window_size[1] as f32,
+        rect[2] * window_size[0] as f32,
+        rect[3] * window_size[1] as f32,
+    ]
+}
+
 pub struct Panel {
     id: u32,
+    /// Set when the element changes, see [Element::is_dirty].
+    dirty: bool,
+    key: String,
+    z_index: i32,
+    clip_rect: Option<[f32; 4]>,
+    camera: Option<CameraHandle>,
     pub geometry_type: GeometryType,
-    relative_position: [f32; 2],
+    placement: Placement,
     color: [f32; 4],
-    relative_scale: [f32; 2],
     texture_name: String,
+    material: Option<String>,
+    transform: Transform,
+    style: QuadStyle,
 }
 
 impl Panel {
     pub fn new(relative_position: [f32; 2], color: [f32; 4], relative_scale: [f32; 2], texture_name: &str) -> Self {
         Self {
             id: 0,
+            dirty: true,
+            key: String::new(),
+            z_index: 0,
+            clip_rect: None,
+            camera: None,
             geometry_type: GeometryType::Quad,
-            relative_position,
+            placement: Placement::new(relative_position, relative_scale),
             color,
-            relative_scale,
             texture_name: texture_name.to_string(),
+            material: None,
+            transform: Transform::default(),
+            style: QuadStyle::default(),
         }
     }
+
+    /// Draws the element with a material registered through
+    /// [RenderState::register_material](crate::RenderState::register_material).
+    pub fn with_material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Rounds the element's corners and gives it a border.
+    pub fn with_style(mut self, style: QuadStyle) -> Self {
+        self.style = style;
+        self
+    }
 }
 
 impl Element for Panel {
@@ -114,7 +333,7 @@ impl Element for Panel {
     }
 
     fn get_position(&mut self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32]
+        [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32]
     }
 
     fn get_color(&self) -> [f32; 4] {
@@ -122,13 +341,29 @@ impl Element for Panel {
     }
 
     fn get_scale(&self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_scale[0] * window_size[0] as f32, self.relative_scale[1] * window_size[1] as f32]
+        [self.placement.scale()[0] * window_size[0] as f32, self.placement.scale()[1] * window_size[1] as f32]
     }
 
     fn get_texture_name(&self) -> Option<String> {
         Some(self.texture_name.clone())
     }
 
+    fn get_material(&self) -> Option<String> {
+        self.material.clone()
+    }
+
+    fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn get_style(&self) -> QuadStyle {
+        self.style
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
+
     fn get_text(&mut self) -> Option<&String> {
         None
     }
@@ -137,9 +372,13 @@ impl Element for Panel {
         None
     }
 
+    fn get_text_size(&self) -> Option<f32> {
+        None
+    }
+
     fn get_layer(&self, input: [f32; 4], window_size: [u32; 2]) -> bool {
-        let position_self = [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32];
-        let scale_self = [(self.relative_scale[0] * window_size[0] as f32) / 2.0, (self.relative_scale[1] * window_size[1] as f32) / 2.0];
+        let position_self = [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32];
+        let scale_self = [(self.placement.scale()[0] * window_size[0] as f32) / 2.0, (self.placement.scale()[1] * window_size[1] as f32) / 2.0];
         let position_input = [input[0] * window_size[0] as f32, input[1] * window_size[1] as f32];
         let scale_input = [(input[2] * window_size[0] as f32) / 2.0, (input[3] * window_size[1] as f32) / 2.0];
         if position_self[0] - scale_self[0] > position_input[0] - scale_input[0]
@@ -148,17 +387,74 @@ impl Element for Panel {
             || position_self[1] + scale_self[1] < position_input[1] + scale_input[1] {
                 return true;
             }
-        return false;
+        false
     }
 
     fn get_element_type(&self) -> ElementType {
         ElementType::Panel(self.id)
     }
 
+    fn get_clip_rect(&self, window_size: [u32; 2]) -> Option<[f32; 4]> {
+        self.clip_rect.map(|clip_rect| relative_rect_to_pixels(clip_rect, window_size))
+    }
+
+    fn get_camera(&self) -> Option<CameraHandle> {
+        self.camera
+    }
+
+    fn get_relative_rect(&self) -> Option<[f32; 4]> {
+        Some(self.placement.rect())
+    }
+
+    fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    fn get_state(&self) -> Option<WidgetState> {
+        None
+    }
+
+    fn get_highlight(&self) -> Option<f32> {
+        None
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
     fn set_id(&mut self, id: u32) {
+        self.dirty |= self.id != id;
         self.id = id
     }
 
+    fn set_parent_rect(&mut self, relative_parent_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_parent_rect(relative_parent_rect);
+    }
+
+    fn set_relative_rect(&mut self, relative_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_rect(relative_rect);
+    }
+
+    fn set_key(&mut self, key: String) {
+        self.key = key;
+    }
+
+    fn set_state(&mut self, _state: WidgetState) {}
+
+    fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    fn set_clip_rect(&mut self, relative_clip_rect: Option<[f32; 4]>) {
+        self.dirty |= self.clip_rect != relative_clip_rect;
+        self.clip_rect = relative_clip_rect;
+    }
+
+    fn set_camera(&mut self, camera: Option<CameraHandle>) {
+        self.dirty |= self.camera != camera;
+        self.camera = camera;
+    }
+
     fn set_highlight(&mut self, _a_value: f32) -> bool {
         false
     }
@@ -167,15 +463,18 @@ impl Element for Panel {
         unimplemented!()
     }
 
+    fn set_scale_factor(&mut self, _scale_factor: f32) {}
+
     fn handle_click(&self) -> InteractionResult {
         if self.id == 0 {
-            return InteractionResult::Propogate(UiEvent::TitleBar)
+            InteractionResult::Propogate(UiEvent::TitleBar)
         } else {
             InteractionResult::None
         }
     }
 
     fn is_cursor_within_bounds(&self, cursor_position: [f32; 2], element_pos: [f32; 2], element_scale: [f32;2]) -> bool {
+        let cursor_position = self.transform.untransform_point(cursor_position, element_pos, element_scale);
         if cursor_position[0] <= element_pos[0] + (element_scale[0] / 2.0) 
             && cursor_position[0] >= element_pos[0] - (element_scale[0] / 2.0)
             && cursor_position[1] <= element_pos[1] + (element_scale[1] / 2.0) 
@@ -187,6 +486,223 @@ impl Element for Panel {
     }
 }
 
+/// Rounded corners, a border, a drop shadow and a gradient fill for quad elements,
+/// sizes are in logical pixels. The shape is evaluated as a signed distance field so the
+/// edges stay smooth at any size.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QuadStyle {
+    pub corner_radius: f32,
+    pub border_width: f32,
+    pub border_color: [f32; 4],
+    pub shadow: Option<Shadow>,
+    pub gradient: Option<Gradient>,
+}
+
+impl QuadStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    /// Draws a border of `width` pixels inside the element's edge.
+    pub fn with_border(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.border_width = width;
+        self.border_color = color;
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    /// Fills the element with a gradient, it's multiplied with the element's
+    /// color and texture so a white element shows the gradient as is.
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    /// Converts the sizes from logical pixels to pixels covering `scale_factor` times as many.
+    pub(crate) fn scaled(mut self, scale_factor: f32) -> Self {
+        self.corner_radius *= scale_factor;
+        self.border_width *= scale_factor;
+        if let Some(shadow) = self.shadow.as_mut() {
+            shadow.offset = shadow.offset.map(|offset| offset * scale_factor);
+            shadow.blur_radius *= scale_factor;
+            shadow.spread *= scale_factor;
+        }
+        self
+    }
+}
+
+/// A soft shadow drawn under an element, in logical pixels. The spread grows the
+/// shadow past the element's edges before it's blurred.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub offset: [f32; 2],
+    pub blur_radius: f32,
+    pub spread: f32,
+    pub color: [f32; 4],
+}
+
+impl Shadow {
+    pub fn new(offset: [f32; 2], blur_radius: f32, spread: f32, color: [f32; 4]) -> Self {
+        Self { offset, blur_radius, spread, color }
+    }
+
+    /// A shadow cast straight down that gets further away and softer
+    /// the higher the element is raised.
+    pub fn from_elevation(elevation: f32) -> Self {
+        Self::new([0.0, elevation], elevation * 2.0, 0.0, [0.0, 0.0, 0.0, 0.5])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// Runs along `angle` in degrees, 0 goes left to right and 90 top to bottom.
+    Linear { angle: f32 },
+    /// Spreads out from `center` to the farthest corner, the center is
+    /// relative to the element so `[0.5, 0.5]` is its middle.
+    Radial { center: [f32; 2] },
+}
+
+/// A linear or radial gradient with up to [MAX_GRADIENT_STOPS] color stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    kind: GradientKind,
+    stops: [(f32, [f32; 4]); MAX_GRADIENT_STOPS],
+    stop_count: usize,
+}
+
+impl Gradient {
+    pub fn linear(angle: f32) -> Self {
+        Self::new(GradientKind::Linear { angle })
+    }
+
+    pub fn radial(center: [f32; 2]) -> Self {
+        Self::new(GradientKind::Radial { center })
+    }
+
+    fn new(kind: GradientKind) -> Self {
+        Self {
+            kind,
+            stops: [(0.0, [0.0; 4]); MAX_GRADIENT_STOPS],
+            stop_count: 0,
+        }
+    }
+
+    /// Adds a color stop at `offset` between 0.0 and 1.0, stops must be added
+    /// in order. Panics when the gradient already has [MAX_GRADIENT_STOPS] stops.
+    pub fn with_stop(mut self, offset: f32, color: [f32; 4]) -> Self {
+        assert!(self.stop_count < MAX_GRADIENT_STOPS, "gradients can't have more than {MAX_GRADIENT_STOPS} stops");
+        self.stops[self.stop_count] = (offset, color);
+        self.stop_count += 1;
+        self
+    }
+
+    pub fn kind(&self) -> GradientKind {
+        self.kind
+    }
+
+    pub fn stops(&self) -> &[(f32, [f32; 4])] {
+        &self.stops[..self.stop_count]
+    }
+
+    /// Packs the gradient into the layout [InstanceRaw](crate::definitions::InstanceRaw) uses.
+    pub(crate) fn to_raw(self) -> ([u32; 4], [f32; 4], [f32; 4]) {
+        let mut colors = [0; 4];
+        let mut offsets = [0.0; 4];
+        for (index, (offset, color)) in self.stops().iter().enumerate() {
+            colors[index] = u32::from_le_bytes(color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8));
+            offsets[index] = *offset;
+        }
+
+        let stop_count = self.stop_count as f32;
+        let parameters = match self.kind {
+            GradientKind::Linear { angle } => [1.0, stop_count, angle.to_radians(), 0.0],
+            GradientKind::Radial { center } => [2.0, stop_count, center[0], center[1]],
+        };
+        (colors, offsets, parameters)
+    }
+}
+
+/// Rotation and skew applied to an element around its pivot, angles are in
+/// degrees and positive rotations turn clockwise on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub rotation: f32,
+    /// Skew along the x and y axes.
+    pub skew: [f32; 2],
+    /// Point the element turns around, relative to the element so
+    /// `[0.5, 0.5]` is its center and `[0.0, 0.0]` its top left corner.
+    pub pivot: [f32; 2],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            rotation: 0.0,
+            skew: [0.0, 0.0],
+            pivot: [0.5, 0.5],
+        }
+    }
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_skew(mut self, skew: [f32; 2]) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    pub fn with_pivot(mut self, pivot: [f32; 2]) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    fn to_mat2(self) -> Mat2 {
+        let skew = Mat2::from_cols(
+            Vec2::new(1.0, self.skew[1].to_radians().tan()),
+            Vec2::new(self.skew[0].to_radians().tan(), 1.0),
+        );
+        Mat2::from_angle(self.rotation.to_radians()) * skew
+    }
+
+    /// Columns of the rotation and skew matrix, as stored in
+    /// [InstanceRaw](crate::definitions::InstanceRaw).
+    pub(crate) fn matrix(self) -> [f32; 4] {
+        self.to_mat2().to_cols_array()
+    }
+
+    /// How far the element's center moves when it's transformed around its
+    /// pivot instead of its center, for an element `scale` pixels in size.
+    pub(crate) fn pivot_offset(self, scale: [f32; 2]) -> [f32; 2] {
+        let pivot = (Vec2::from(self.pivot) - 0.5) * Vec2::from(scale);
+        (pivot - self.to_mat2() * pivot).into()
+    }
+
+    /// Maps a point on screen back to where it would be if the element at
+    /// `element_pos` wasn't transformed, used to hit test transformed elements.
+    pub fn untransform_point(self, point: [f32; 2], element_pos: [f32; 2], element_scale: [f32; 2]) -> [f32; 2] {
+        let center = Vec2::from(element_pos) + Vec2::from(self.pivot_offset(element_scale));
+        let local = self.to_mat2().inverse() * (Vec2::from(point) - center);
+        (Vec2::from(element_pos) + local).into()
+    }
+}
+
 pub enum UiEvent {
     CloseRequested,
     SetMinimized,
@@ -200,6 +716,16 @@ pub enum InteractionResult {
     None
 }
 
+/// State an element picks up from input rather than from how it was built,
+/// it's carried over to the element replacing it when the interface is rebuilt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetState {
+    /// Text typed into a [TextBox].
+    Text(String),
+    /// Alpha a [Button] was highlighted with.
+    Highlight(f32),
+}
+
 #[derive(PartialEq)]
 pub enum ElementType {
     Panel(u32),
@@ -211,29 +737,69 @@ pub enum ElementType {
 
 pub struct Button {
     id: u32,
+    /// Set when the element changes, see [Element::is_dirty].
+    dirty: bool,
+    key: String,
+    z_index: i32,
+    clip_rect: Option<[f32; 4]>,
+    camera: Option<CameraHandle>,
     pub geometry_type: GeometryType,
-    relative_position: [f32; 2],
+    placement: Placement,
     color: [f32; 4],
-    relative_scale: [f32; 2],
+    highlight: Option<f32>,
     on_click: Option<Box<dyn Fn() + Send + Sync>>,
     on_click_propogate: Option<Box<dyn Fn() -> UiEvent + 'static>>,
     texture_name: String,
+    material: Option<String>,
+    transform: Transform,
+    style: QuadStyle,
 }
 
 impl Button {
     pub fn new(relative_position: [f32; 2], color: [f32; 4], relative_scale: [f32; 2], texture_name: &str) -> Self {
         Self {
             id: 0,
+            dirty: true,
+            key: String::new(),
+            z_index: 0,
+            clip_rect: None,
+            camera: None,
             geometry_type: GeometryType::Quad,
-            relative_position,
+            placement: Placement::new(relative_position, relative_scale),
             color,
-            relative_scale,
+            highlight: None,
             on_click: None,
             on_click_propogate: None,
             texture_name: texture_name.to_string(),
+            material: None,
+            transform: Transform::default(),
+            style: QuadStyle::default(),
         }
     }
 
+    /// Draws the element with a material registered through
+    /// [RenderState::register_material](crate::RenderState::register_material).
+    pub fn with_material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Rounds the element's corners and gives it a border.
+    pub fn with_style(mut self, style: QuadStyle) -> Self {
+        self.style = style;
+        self
+    }
+
     pub fn with_prop_fn(mut self, function: impl Fn() -> UiEvent + 'static) -> Self {
         self.on_click_propogate = Some(Box::new(function));
         self
@@ -250,6 +816,10 @@ impl Element for Button {
         None
     }
 
+    fn get_text_size(&self) -> Option<f32> {
+        None
+    }
+
     fn get_id(&self) -> u32 {
         self.id
     }
@@ -259,11 +829,11 @@ impl Element for Button {
     }
 
     fn get_position(&mut self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32]
+        [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32]
     }
 
     fn get_scale(&self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_scale[0] * window_size[0] as f32, self.relative_scale[1] * window_size[1] as f32]
+        [self.placement.scale()[0] * window_size[0] as f32, self.placement.scale()[1] * window_size[1] as f32]
     }
 
     fn get_color(&self) -> [f32; 4] {
@@ -271,8 +841,8 @@ impl Element for Button {
     }
 
     fn get_layer(&self, input: [f32; 4], window_size: [u32; 2]) -> bool {
-        let position_self = [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32];
-        let scale_self = [(self.relative_scale[0] * window_size[0] as f32) / 2.0, (self.relative_scale[1] * window_size[1] as f32) / 2.0];
+        let position_self = [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32];
+        let scale_self = [(self.placement.scale()[0] * window_size[0] as f32) / 2.0, (self.placement.scale()[1] * window_size[1] as f32) / 2.0];
         let position_input = [input[0] * window_size[0] as f32, input[1] * window_size[1] as f32];
         let scale_input = [(input[2] * window_size[0] as f32) / 2.0, (input[3] * window_size[1] as f32) / 2.0];
         if position_self[0] - scale_self[0] > position_input[0] - scale_input[0]
@@ -281,19 +851,82 @@ impl Element for Button {
             || position_self[1] + scale_self[1] < position_input[1] + scale_input[1] {
                 return true;
             }
-        return false;
+        false
     }
 
     fn get_element_type(&self) -> ElementType {
         ElementType::Button(self.id)
     }
 
+    fn get_clip_rect(&self, window_size: [u32; 2]) -> Option<[f32; 4]> {
+        self.clip_rect.map(|clip_rect| relative_rect_to_pixels(clip_rect, window_size))
+    }
+
+    fn get_camera(&self) -> Option<CameraHandle> {
+        self.camera
+    }
+
+    fn get_relative_rect(&self) -> Option<[f32; 4]> {
+        Some(self.placement.rect())
+    }
+
+    fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    fn get_state(&self) -> Option<WidgetState> {
+        self.highlight.map(WidgetState::Highlight)
+    }
+
+    fn get_highlight(&self) -> Option<f32> {
+        self.highlight
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
     fn set_id(&mut self, id: u32) {
+        self.dirty |= self.id != id;
         self.id = id;
     }
 
+    fn set_parent_rect(&mut self, relative_parent_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_parent_rect(relative_parent_rect);
+    }
+
+    fn set_relative_rect(&mut self, relative_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_rect(relative_rect);
+    }
+
+    fn set_key(&mut self, key: String) {
+        self.key = key;
+    }
+
+    fn set_state(&mut self, state: WidgetState) {
+        if let WidgetState::Highlight(alpha) = state {
+            self.dirty |= self.highlight != Some(alpha);
+            self.highlight = Some(alpha);
+        }
+    }
+
+    fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    fn set_clip_rect(&mut self, relative_clip_rect: Option<[f32; 4]>) {
+        self.dirty |= self.clip_rect != relative_clip_rect;
+        self.clip_rect = relative_clip_rect;
+    }
+
+    fn set_camera(&mut self, camera: Option<CameraHandle>) {
+        self.dirty |= self.camera != camera;
+        self.camera = camera;
+    }
+
     fn set_highlight(&mut self, a_value: f32) -> bool {
-        self.color[3] = a_value;
+        self.dirty |= self.highlight != Some(a_value);
+        self.highlight = Some(a_value);
         true
     }
 
@@ -301,6 +934,8 @@ impl Element for Button {
         unimplemented!()
     }
 
+    fn set_scale_factor(&mut self, _scale_factor: f32) {}
+
     fn handle_click(&self) -> InteractionResult {
         if let Some(function) = &self.on_click {
             (function)();
@@ -314,6 +949,7 @@ impl Element for Button {
     }
 
     fn is_cursor_within_bounds(&self, cursor_position: [f32; 2], element_pos: [f32; 2], element_scale: [f32;2]) -> bool {
+        let cursor_position = self.transform.untransform_point(cursor_position, element_pos, element_scale);
         if cursor_position[0] <= element_pos[0] + (element_scale[0] / 2.0) 
             && cursor_position[0] >= element_pos[0] - (element_scale[0] / 2.0)
             && cursor_position[1] <= element_pos[1] + (element_scale[1] / 2.0) 
@@ -327,6 +963,22 @@ impl Element for Button {
     fn get_texture_name(&self) -> Option<String> {
         Some(self.texture_name.clone())
     }
+
+    fn get_material(&self) -> Option<String> {
+        self.material.clone()
+    }
+
+    fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn get_style(&self) -> QuadStyle {
+        self.style
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
     
     fn get_text(&mut self) -> Option<&String> {
         None
@@ -335,29 +987,52 @@ impl Element for Button {
 
 pub struct Label {
     id: u32,
+    /// Set when the element changes, see [Element::is_dirty].
+    dirty: bool,
+    key: String,
+    z_index: i32,
+    clip_rect: Option<[f32; 4]>,
+    camera: Option<CameraHandle>,
     pub geometry_type: GeometryType,
     text: String,
     color: [f32; 4],
-    relative_position: [f32; 2],
-    relative_scale: [f32; 2],
-    relative_bounds: Option<[f32; 2]>,
+    placement: Placement,
+    /// In logical pixels.
+    text_size: f32,
+    scale_factor: f32,
 }
 
 impl Label {
     pub fn new(text: &str, relative_position: [f32; 2], relative_scale: [f32; 2], color: [f32; 4]) -> Self {
         Self {
             id: 0,
+            dirty: true,
+            key: String::new(),
+            z_index: 0,
+            clip_rect: None,
+            camera: None,
             geometry_type: GeometryType::Label,
             text: text.to_string(),
             color,
-            relative_position,
-            relative_scale,
-            relative_bounds: None,
+            placement: Placement::new(relative_position, relative_scale),
+            text_size: DEFAULT_TEXT_SIZE,
+            scale_factor: 1.0,
         }
     }
 
     pub fn with_bounds(mut self, relative_bounds: [f32; 2]) -> Self {
-        self.relative_bounds = Some([relative_bounds[0], relative_bounds[1]]);
+        self.placement.bounds = Some([relative_bounds[0], relative_bounds[1]]);
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Sets the text size in logical pixels, [DEFAULT_TEXT_SIZE] otherwise.
+    pub fn with_text_size(mut self, text_size: f32) -> Self {
+        self.text_size = text_size;
         self
     }
 }
@@ -372,9 +1047,11 @@ impl Element for Label {
     }
 
     fn get_position(&mut self, window_size: [u32; 2]) -> [f32; 2] {
-        let text_length = (self.text.chars().count() as f32 * 15.0) / 2.0;
-        let text_height = 30.0 / 2.0;
-        [self.relative_position[0] * window_size[0] as f32 - text_length, self.relative_position[1] * window_size[1] as f32 - text_height]
+        // Glyphs of the monospaced font are half as wide as the text size
+        let text_size = self.text_size * self.scale_factor;
+        let text_length = (self.text.chars().count() as f32 * text_size / 2.0) / 2.0;
+        let text_height = text_size / 2.0;
+        [self.placement.position()[0] * window_size[0] as f32 - text_length, self.placement.position()[1] * window_size[1] as f32 - text_height]
     }
 
     fn get_color(&self) -> [f32; 4] {
@@ -382,45 +1059,124 @@ impl Element for Label {
     }
 
     fn get_scale(&self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_scale[0] * window_size[0] as f32, self.relative_scale[1] * window_size[1] as f32]
+        [self.placement.scale()[0] * window_size[0] as f32, self.placement.scale()[1] * window_size[1] as f32]
     }
 
     fn get_texture_name(&self) -> Option<String> {
         None
     }
 
+    fn get_material(&self) -> Option<String> {
+        None
+    }
+
+    fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn get_style(&self) -> QuadStyle {
+        QuadStyle::default()
+    }
+
+    fn get_transform(&self) -> Transform {
+        Transform::default()
+    }
+
     fn get_text(&mut self) -> Option<&String> {
         Some(&self.text)
     }
 
     fn get_bounds(&self, window_size: [u32; 2]) -> Option<[f32; 2]> {
-        if let Some(bounds) = self.relative_bounds {
-            Some([bounds[0] * window_size[0] as f32, bounds[1] * window_size[1] as f32])
-        } else {
-            None
-        }
+        self.placement.bounds().map(|bounds| [bounds[0] * window_size[0] as f32, bounds[1] * window_size[1] as f32])
+    }
+
+    fn get_text_size(&self) -> Option<f32> {
+        Some(self.text_size * self.scale_factor)
     }
 
     fn get_layer(&self, _input: [f32; 4], _window_size: [u32; 2]) -> bool {
-        return false;
+        false
     }
 
     fn get_element_type(&self) -> ElementType {
         ElementType::Label(self.id)
     }
 
+    fn get_clip_rect(&self, window_size: [u32; 2]) -> Option<[f32; 4]> {
+        self.clip_rect.map(|clip_rect| relative_rect_to_pixels(clip_rect, window_size))
+    }
+
+    fn get_camera(&self) -> Option<CameraHandle> {
+        self.camera
+    }
+
+    fn get_relative_rect(&self) -> Option<[f32; 4]> {
+        Some(self.placement.rect())
+    }
+
+    fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    fn get_state(&self) -> Option<WidgetState> {
+        None
+    }
+
+    fn get_highlight(&self) -> Option<f32> {
+        None
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
     fn set_id(&mut self, id: u32) {
+        self.dirty |= self.id != id;
         self.id = id;
     }
 
+    fn set_parent_rect(&mut self, relative_parent_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_parent_rect(relative_parent_rect);
+    }
+
+    fn set_relative_rect(&mut self, relative_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_rect(relative_rect);
+    }
+
+    fn set_key(&mut self, key: String) {
+        self.key = key;
+    }
+
+    fn set_state(&mut self, _state: WidgetState) {}
+
+    fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    fn set_clip_rect(&mut self, relative_clip_rect: Option<[f32; 4]>) {
+        self.dirty |= self.clip_rect != relative_clip_rect;
+        self.clip_rect = relative_clip_rect;
+    }
+
+    fn set_camera(&mut self, camera: Option<CameraHandle>) {
+        self.dirty |= self.camera != camera;
+        self.camera = camera;
+    }
+
     fn set_highlight(&mut self, _a_value: f32) -> bool {
         false
     }
 
     fn set_text(&mut self, text: &str, _window_size: [u32; 2]) {
+        self.dirty = true;
         self.text.push_str(text);
     }
 
+    fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.dirty |= self.scale_factor != scale_factor;
+        self.scale_factor = scale_factor;
+    }
+
     fn handle_click(&self) -> InteractionResult {
         InteractionResult::None
     }
@@ -432,24 +1188,58 @@ impl Element for Label {
 
 pub struct Icon {
     id: u32,
+    /// Set when the element changes, see [Element::is_dirty].
+    dirty: bool,
+    key: String,
+    z_index: i32,
+    clip_rect: Option<[f32; 4]>,
+    camera: Option<CameraHandle>,
     pub geometry_type: GeometryType,
-    relative_position: [f32; 2],
+    placement: Placement,
     color: [f32; 4],
     relative_scale: [f32; 2],
+    scale_factor: f32,
     texture_name: String,
+    material: Option<String>,
+    transform: Transform,
 }
 
 impl Icon {
     pub fn new(relative_position: [f32; 2], color: [f32; 4], relative_scale: [f32; 2], texture_name: &str) -> Self {
         Self {
             id: 0,
+            dirty: true,
+            key: String::new(),
+            z_index: 0,
+            clip_rect: None,
+            camera: None,
             geometry_type: GeometryType::Quad,
-            relative_position,
+            placement: Placement::new(relative_position, [0.0, 0.0]),
             color,
             relative_scale,
+            scale_factor: 1.0,
             texture_name: texture_name.to_string(),
+            material: None,
+            transform: Transform::default(),
         }
     }
+
+    /// Draws the element with a material registered through
+    /// [RenderState::register_material](crate::RenderState::register_material).
+    pub fn with_material(mut self, material: &str) -> Self {
+        self.material = Some(material.to_string());
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
 }
 
 impl Element for Icon {
@@ -457,6 +1247,10 @@ impl Element for Icon {
         None
     }
 
+    fn get_text_size(&self) -> Option<f32> {
+        None
+    }
+
     fn get_id(&self) -> u32 {
         self.id
     }
@@ -466,11 +1260,11 @@ impl Element for Icon {
     }
 
     fn get_position(&mut self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32]
+        [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32]
     }
 
     fn get_scale(&self, _window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_scale[0], self.relative_scale[1]]
+        [self.relative_scale[0] * self.scale_factor, self.relative_scale[1] * self.scale_factor]
     }
 
     fn get_color(&self) -> [f32; 4] {
@@ -478,8 +1272,9 @@ impl Element for Icon {
     }
 
     fn get_layer(&self, input: [f32; 4], window_size: [u32; 2]) -> bool {
-        let position_self = [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32];
-        let scale_self = [(self.relative_scale[0] * window_size[0] as f32) / 2.0, (self.relative_scale[1] * window_size[1] as f32) / 2.0];
+        let position_self = [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32];
+        // The icon's size is already in pixels
+        let scale_self = [self.relative_scale[0] * self.scale_factor / 2.0, self.relative_scale[1] * self.scale_factor / 2.0];
         let position_input = [input[0] * window_size[0] as f32, input[1] * window_size[1] as f32];
         let scale_input = [(input[2] * window_size[0] as f32) / 2.0, (input[3] * window_size[1] as f32) / 2.0];
         if position_self[0] - scale_self[0] > position_input[0] - scale_input[0]
@@ -488,17 +1283,74 @@ impl Element for Icon {
             || position_self[1] + scale_self[1] < position_input[1] + scale_input[1] {
                 return true;
             }
-        return false;
+        false
     }
 
     fn get_element_type(&self) -> ElementType {
         ElementType::Icon(self.id)
     }
 
+    fn get_clip_rect(&self, window_size: [u32; 2]) -> Option<[f32; 4]> {
+        self.clip_rect.map(|clip_rect| relative_rect_to_pixels(clip_rect, window_size))
+    }
+
+    fn get_camera(&self) -> Option<CameraHandle> {
+        self.camera
+    }
+
+    fn get_relative_rect(&self) -> Option<[f32; 4]> {
+        None
+    }
+
+    fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    fn get_state(&self) -> Option<WidgetState> {
+        None
+    }
+
+    fn get_highlight(&self) -> Option<f32> {
+        None
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
     fn set_id(&mut self, id: u32) {
+        self.dirty |= self.id != id;
         self.id = id;
     }
 
+    fn set_parent_rect(&mut self, relative_parent_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_parent_rect(relative_parent_rect);
+    }
+
+    fn set_relative_rect(&mut self, relative_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_rect(relative_rect);
+    }
+
+    fn set_key(&mut self, key: String) {
+        self.key = key;
+    }
+
+    fn set_state(&mut self, _state: WidgetState) {}
+
+    fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    fn set_clip_rect(&mut self, relative_clip_rect: Option<[f32; 4]>) {
+        self.dirty |= self.clip_rect != relative_clip_rect;
+        self.clip_rect = relative_clip_rect;
+    }
+
+    fn set_camera(&mut self, camera: Option<CameraHandle>) {
+        self.dirty |= self.camera != camera;
+        self.camera = camera;
+    }
+
     fn set_highlight(&mut self, _a_value: f32) -> bool {
         false
     }
@@ -507,6 +1359,11 @@ impl Element for Icon {
         unimplemented!()
     }
 
+    fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.dirty |= self.scale_factor != scale_factor;
+        self.scale_factor = scale_factor;
+    }
+
     fn handle_click(&self) -> InteractionResult {
         InteractionResult::None
     }
@@ -518,6 +1375,22 @@ impl Element for Icon {
     fn get_texture_name(&self) -> Option<String> {
         Some(self.texture_name.clone())
     }
+
+    fn get_material(&self) -> Option<String> {
+        self.material.clone()
+    }
+
+    fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn get_style(&self) -> QuadStyle {
+        QuadStyle::default()
+    }
+
+    fn get_transform(&self) -> Transform {
+        self.transform
+    }
     
     fn get_text(&mut self) -> Option<&String> {
         None
@@ -526,14 +1399,21 @@ impl Element for Icon {
 
 pub struct TextBox {
     id: u32,
+    /// Set when the element changes, see [Element::is_dirty].
+    dirty: bool,
+    key: String,
+    z_index: i32,
+    clip_rect: Option<[f32; 4]>,
+    camera: Option<CameraHandle>,
     pub geometry_type: GeometryType,
     text: String,
     placeholder: String,
     final_text: String,
     color: [f32; 4],
-    relative_position: [f32; 2],
-    relative_scale: [f32; 2],
-    relative_bounds: Option<[f32; 2]>,
+    placement: Placement,
+    /// In logical pixels.
+    text_size: f32,
+    scale_factor: f32,
     timer: SystemTime,
     blink_rate: Duration,
     is_cursor_visible: bool,
@@ -543,14 +1423,19 @@ impl TextBox {
     pub fn new(placeholder_text: &str, relative_position: [f32; 2], relative_scale: [f32; 2], color: [f32; 4]) -> Self {
         Self {
             id: 0,
+            dirty: true,
+            key: String::new(),
+            z_index: 0,
+            clip_rect: None,
+            camera: None,
             geometry_type: GeometryType::Label,
             text: String::new(),
             placeholder: placeholder_text.to_string(),
             final_text: String::new(),
             color,
-            relative_position,
-            relative_scale,
-            relative_bounds: None,
+            placement: Placement::new(relative_position, relative_scale),
+            text_size: DEFAULT_TEXT_SIZE,
+            scale_factor: 1.0,
             timer: SystemTime::now(),
             blink_rate: Duration::from_millis(500),
             is_cursor_visible: false,
@@ -558,7 +1443,18 @@ impl TextBox {
     }
 
     pub fn with_bounds(mut self, relative_bounds: [f32; 2]) -> Self {
-        self.relative_bounds = Some(relative_bounds);
+        self.placement.bounds = Some(relative_bounds);
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Sets the text size in logical pixels, [DEFAULT_TEXT_SIZE] otherwise.
+    pub fn with_text_size(mut self, text_size: f32) -> Self {
+        self.text_size = text_size;
         self
     }
 }
@@ -574,8 +1470,8 @@ impl Element for TextBox {
 
     fn get_position(&mut self, window_size: [u32; 2]) -> [f32; 2] {
         let text_length = 0.0;
-        let text_height = 30.0 / 2.0;
-        [self.relative_position[0] * window_size[0] as f32 - text_length, self.relative_position[1] * window_size[1] as f32 - text_height]
+        let text_height = self.text_size * self.scale_factor / 2.0;
+        [self.placement.position()[0] * window_size[0] as f32 - text_length, self.placement.position()[1] * window_size[1] as f32 - text_height]
     }
 
     fn get_color(&self) -> [f32; 4] {
@@ -583,13 +1479,29 @@ impl Element for TextBox {
     }
 
     fn get_scale(&self, window_size: [u32; 2]) -> [f32; 2] {
-        [self.relative_scale[0] * window_size[0] as f32, self.relative_scale[1] * window_size[1] as f32]
+        [self.placement.scale()[0] * window_size[0] as f32, self.placement.scale()[1] * window_size[1] as f32]
     }
 
     fn get_texture_name(&self) -> Option<String> {
         None
     }
 
+    fn get_material(&self) -> Option<String> {
+        None
+    }
+
+    fn get_z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    fn get_style(&self) -> QuadStyle {
+        QuadStyle::default()
+    }
+
+    fn get_transform(&self) -> Transform {
+        Transform::default()
+    }
+
     fn get_text(&mut self) -> Option<&String> {
         if self.is_cursor_visible {
             self.final_text = format!("{}{}", self.text.clone(), "|");
@@ -604,7 +1516,7 @@ impl Element for TextBox {
                     self.timer = SystemTime::now();
 
                     if self.is_cursor_visible {
-                        self.final_text.push_str("|");
+                        self.final_text.push('|');
                     }
                 }
             }
@@ -613,23 +1525,27 @@ impl Element for TextBox {
         }
 
         if self.text.is_empty() {
-            return Some(&self.placeholder)
+            Some(&self.placeholder)
         } else {
-            return Some(&self.final_text)
+            Some(&self.final_text)
         }
     }
 
     fn get_bounds(&self, window_size: [u32; 2]) -> Option<[f32; 2]> {
-        if let Some(relative_bounds) = self.relative_bounds {
+        if let Some(relative_bounds) = self.placement.bounds() {
             Some([relative_bounds[0] * window_size[0] as f32, relative_bounds[1] * window_size[1] as f32])
         } else {
-            self.relative_bounds
+            self.placement.bounds()
         }
     }
 
+    fn get_text_size(&self) -> Option<f32> {
+        Some(self.text_size * self.scale_factor)
+    }
+
     fn get_layer(&self, input: [f32; 4], window_size: [u32; 2]) -> bool {
-        let position_self = [self.relative_position[0] * window_size[0] as f32, self.relative_position[1] * window_size[1] as f32];
-        let scale_self = [(self.relative_scale[0] * window_size[0] as f32) / 2.0, (self.relative_scale[1] * window_size[1] as f32) / 2.0];
+        let position_self = [self.placement.position()[0] * window_size[0] as f32, self.placement.position()[1] * window_size[1] as f32];
+        let scale_self = [(self.placement.scale()[0] * window_size[0] as f32) / 2.0, (self.placement.scale()[1] * window_size[1] as f32) / 2.0];
         let position_input = [input[0] * window_size[0] as f32, input[1] * window_size[1] as f32];
         let scale_input = [(input[2] * window_size[0] as f32) / 2.0, (input[3] * window_size[1] as f32) / 2.0];
         if position_self[0] - scale_self[0] > position_input[0] - scale_input[0]
@@ -638,17 +1554,79 @@ impl Element for TextBox {
             || position_self[1] + scale_self[1] < position_input[1] + scale_input[1] {
                 return true;
             }
-        return false;
+        false
     }
 
     fn get_element_type(&self) -> ElementType {
         ElementType::TextBox(self.id)
     }
 
+    fn get_clip_rect(&self, window_size: [u32; 2]) -> Option<[f32; 4]> {
+        self.clip_rect.map(|clip_rect| relative_rect_to_pixels(clip_rect, window_size))
+    }
+
+    fn get_camera(&self) -> Option<CameraHandle> {
+        self.camera
+    }
+
+    fn get_relative_rect(&self) -> Option<[f32; 4]> {
+        Some(self.placement.rect())
+    }
+
+    fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    fn get_state(&self) -> Option<WidgetState> {
+        (!self.text.is_empty()).then(|| WidgetState::Text(self.text.clone()))
+    }
+
+    fn get_highlight(&self) -> Option<f32> {
+        None
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
     fn set_id(&mut self, id: u32) {
+        self.dirty |= self.id != id;
         self.id = id;
     }
 
+    fn set_parent_rect(&mut self, relative_parent_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_parent_rect(relative_parent_rect);
+    }
+
+    fn set_relative_rect(&mut self, relative_rect: [f32; 4]) {
+        self.dirty |= self.placement.set_rect(relative_rect);
+    }
+
+    fn set_key(&mut self, key: String) {
+        self.key = key;
+    }
+
+    fn set_state(&mut self, state: WidgetState) {
+        if let WidgetState::Text(text) = state {
+            self.dirty = true;
+            self.text = text;
+        }
+    }
+
+    fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    fn set_clip_rect(&mut self, relative_clip_rect: Option<[f32; 4]>) {
+        self.dirty |= self.clip_rect != relative_clip_rect;
+        self.clip_rect = relative_clip_rect;
+    }
+
+    fn set_camera(&mut self, camera: Option<CameraHandle>) {
+        self.dirty |= self.camera != camera;
+        self.camera = camera;
+    }
+
     fn set_highlight(&mut self, _a_value: f32) -> bool {
         false
     }
@@ -678,6 +1656,7 @@ impl Element for TextBox {
         let bounds = self.get_bounds(window_size);
         let mut wrap = false;
 
+        let glyph_width = self.text_size * self.scale_factor / 2.0;
         let mut count = 1;
         for char in self.text.chars() {
             if let Some(bounds_inner) = bounds {
@@ -685,7 +1664,7 @@ impl Element for TextBox {
                     count = 0;
                     wrap = false;
                 }
-                if (count + 1) as f32 * 15.0 >= bounds_inner[0] {
+                if (count + 1) as f32 * glyph_width >= bounds_inner[0] {
                     wrap = true;
                 }
             }
@@ -696,9 +1675,15 @@ impl Element for TextBox {
             text_string = format!("\n{text_string}")
         }
 
+        self.dirty = true;
         self.text.push_str(&text_string);
     }
 
+    fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.dirty |= self.scale_factor != scale_factor;
+        self.scale_factor = scale_factor;
+    }
+
     fn handle_click(&self) -> InteractionResult {
         println!("Label Handle Click");
         InteractionResult::Propogate(UiEvent::SetSelected(self.id, self.get_element_type()))
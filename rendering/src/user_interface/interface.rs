@@ -1,21 +1,138 @@
 use core::option::Option::Some;
-use std::collections::HashMap;
+use std::{collections::{hash_map::Entry, HashMap, HashSet}, mem, ops::Range};
 
-use wgpu::{Device, Queue};
+use glam::Mat4;
+use wgpu::{util::DeviceExt, Device, Queue};
+use winit::dpi::PhysicalSize;
 use wgpu_text::{glyph_brush::{ab_glyph::FontRef, Section, Text}, BrushBuilder, TextBrush};
 
-use crate::{definitions::{GeometryType, Instance, InstanceRaw, UiAtlas, Vertex}, user_interface::{elements::Element, UserInterface}};
+use crate::{camera::{Camera2D, Camera2DUniform, CameraHandle}, definitions::{GeometryType, Instance, InstanceRaw, UiAtlas, Vertex, DEFAULT_TEXT_SIZE, SHADOW_BLUR_EXTENT}, pipeline::{PipelineRegistry, DEPTH_FORMAT}, user_interface::{elements::{Element, ElementType, WidgetState}, layout::{ItemStyle, Layout}, UserInterface}};
+
+/// A label queued for the text brush.
+struct LabelData {
+    text: String,
+    color: [f32; 4],
+    bounds: Option<[f32; 2]>,
+    position: [f32; 2],
+    text_size: f32,
+    depth: f32,
+    clip_rect: Option<[f32; 4]>,
+    camera: Option<CameraHandle>,
+    layer: usize,
+}
+
+/// Layer, scissor rect as `[x, y, width, height]` and camera of text drawn with the same brush.
+type TextGroup = (usize, [u32; 4], Option<CameraHandle>);
+
+/// A run of instances from one batch drawn with a single call, and the layer
+/// it's drawn in if it's translucent.
+///
+/// Opaque runs are drawn first and write depth, so anything behind them is
+/// hidden. Translucent runs don't write depth, they're drawn afterwards with the
+/// text, a layer at a time from back to front: first a layer's translucent runs
+/// and then its text. A layer only ends where a translucent quad is drawn over
+/// text, so text under a translucent quad is blended into it and text over one
+/// is drawn on top.
+type DrawCall = (BatchKey, Range<u32>, Option<usize>);
+
+/// Kind, id and state of an element from an interface being replaced.
+type PreviousElement = (mem::Discriminant<ElementType>, u32, Option<WidgetState>);
+
+/// A row, column or grid and the elements laid out in it.
+struct LayoutContainer {
+    layout: Layout,
+    /// Element the items are laid out in, None for the window.
+    parent: Option<u32>,
+    camera: Option<CameraHandle>,
+    items: Vec<LayoutChild>,
+}
+
+struct LayoutChild {
+    id: u32,
+    style: ItemStyle,
+    /// Scale the element was built with relative to the container's parent,
+    /// None if it's sized in pixels.
+    natural_scale: Option<[f32; 2]>,
+}
+
+/// Where an element's instances are in the instance buffers, the slots stay
+/// put until elements are added, removed or rescaled.
+struct ElementSlots {
+    batch_key: BatchKey,
+    slot: usize,
+    /// Slot of the element's shadow in the quad batch of its camera.
+    shadow_slot: Option<usize>,
+    depth: f32,
+    translucent: bool,
+}
+
+/// Instances that share geometry, a material and a camera, these are drawn with one call.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct BatchKey {
+    geometry_type: GeometryType,
+    material: Option<String>,
+    camera: Option<CameraHandle>,
+}
 
 pub struct Interface {
     pub elements: Vec<Box<dyn Element>>,
-    instances: HashMap<GeometryType, Vec<InstanceRaw>>,
+    instances: HashMap<BatchKey, Vec<InstanceRaw>>,
+    /// Slots of each element's instances, in the order of `elements`.
+    element_slots: Vec<ElementSlots>,
+    /// Set when elements were added, removed or rescaled, so every instance
+    /// is rebuilt on the next update rather than only the dirty elements'.
+    rebuild_instances: bool,
+    /// Window size the instances were built for.
+    instances_window_size: [u32; 2],
+    /// Back to front runs of instances from the same batch, consecutive
+    /// elements sharing a batch are drawn with a single call.
+    draw_calls: Vec<DrawCall>,
     id_iterator: u32,
     illegal_ids: Vec<u32>,
     vertex_buffers: HashMap<GeometryType, wgpu::Buffer>,
     index_buffers: HashMap<GeometryType, wgpu::Buffer>,
-    instance_buffers: HashMap<GeometryType, wgpu::Buffer>,
+    instance_buffers: HashMap<BatchKey, wgpu::Buffer>,
     brush: Option<TextBrush<FontRef<'static>>>,
+    /// A brush can only draw its text in one call, so text inside clip rects or canvases,
+    /// or in a layer other than the first, gets a brush per layer, rect and camera.
+    /// These are kept around and reused between updates.
+    clipped_brushes: Vec<TextBrush<FontRef<'static>>>,
+    /// Groups drawn by the clipped brushes in use, see [DrawCall] for the layers.
+    text_groups: Vec<TextGroup>,
+    /// Layer of every element when the text was last queued.
+    text_layers: Vec<usize>,
+    text_format: wgpu::TextureFormat,
+    /// Samples per pixel of the targets the text brush draws into.
+    sample_count: u32,
+    /// Clip rects of the scopes elements are currently being added in,
+    /// each one already intersected with those around it.
+    clip_stack: Vec<[f32; 4]>,
+    /// Cameras of the canvases elements are currently being added in.
+    camera_stack: Vec<CameraHandle>,
+    /// Key of each scope elements are currently being added in and how
+    /// many elements were added in it so far, starting with the root scope.
+    key_scopes: Vec<(String, usize)>,
+    /// Id and window relative rect of the parents elements are currently being added in.
+    parent_stack: Vec<(u32, [f32; 4])>,
+    /// Parent id of every element added inside another one.
+    parents: HashMap<u32, u32>,
+    containers: Vec<LayoutContainer>,
+    /// Container, parent depth and item styles of the layouts elements are currently
+    /// being added in. Only elements added at that depth are items.
+    layout_stack: Vec<(usize, usize, Vec<ItemStyle>)>,
+    cameras: Vec<Camera2D>,
+    /// Key of the first canvas each camera was shown in, cameras are matched
+    /// by it in [Interface::reconcile] the same way elements are.
+    camera_keys: Vec<Option<String>>,
+    /// Uniform buffer and bind group of each camera, created when it's first drawn.
+    camera_bindings: Vec<Option<(wgpu::Buffer, wgpu::BindGroup)>>,
+    /// Physical pixels per logical pixel of the display the window is on.
+    scale_factor: f32,
+    /// Zoom of the whole interface on top of the scale factor.
+    zoom: f32,
     atlas: UiAtlas,
+    /// Materials registered as translucent, their instances don't write depth.
+    translucent_materials: HashSet<String>,
 }
 
 impl Interface {
@@ -23,13 +140,52 @@ impl Interface {
         Self {
             elements: Vec::new(),
             instances: HashMap::new(),
+            element_slots: Vec::new(),
+            rebuild_instances: true,
+            instances_window_size: [0, 0],
+            draw_calls: Vec::new(),
             id_iterator: 0,
             illegal_ids: Vec::new(),
             vertex_buffers: HashMap::new(),
             index_buffers: HashMap::new(),
             instance_buffers: HashMap::new(),
             brush: None,
+            clipped_brushes: Vec::new(),
+            text_groups: Vec::new(),
+            text_layers: Vec::new(),
+            text_format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            sample_count: 1,
+            clip_stack: Vec::new(),
+            camera_stack: Vec::new(),
+            key_scopes: vec![(String::new(), 0)],
+            parent_stack: Vec::new(),
+            parents: HashMap::new(),
+            containers: Vec::new(),
+            layout_stack: Vec::new(),
+            cameras: Vec::new(),
+            camera_keys: Vec::new(),
+            camera_bindings: Vec::new(),
+            scale_factor: 1.0,
+            zoom: 1.0,
             atlas,
+            translucent_materials: HashSet::new(),
+        }
+    }
+
+    pub fn atlas(&self) -> &UiAtlas {
+        &self.atlas
+    }
+
+    /// Replaces the atlas after textures were added or removed at runtime.
+    /// If the atlas texture was resized the instance buffers are rebuilt,
+    /// since every texture coordinate moved with it.
+    pub(crate) fn refresh_atlas(&mut self, atlas: UiAtlas, device: &Device, queue: &Queue, window_size: [u32; 2]) {
+        // New pages don't move existing entries, only a new page size does
+        let resized = atlas.size() != self.atlas.size();
+        self.atlas = atlas;
+        self.rebuild_instances |= resized;
+        if resized && !self.instances.is_empty() {
+            self.initialize_interface_buffers(device, queue, window_size);
         }
     }
 
@@ -43,14 +199,569 @@ impl Interface {
             element.set_id(id_number);
             self.illegal_ids.push(id_number);
         } else {
-            while self.illegal_ids.iter().any(|&i| i == self.id_iterator) {
+            while self.illegal_ids.contains(&self.id_iterator) {
                 self.id_iterator += 1;
             }
 
             element.set_id(self.id_iterator);
         }
+        if let Some(&(parent_id, parent_rect)) = self.parent_stack.last() {
+            element.set_parent_rect(parent_rect);
+            self.parents.insert(element.get_id(), parent_id);
+        }
+        element.set_key(self.next_key());
+        if let Some((container, parent_depth, styles)) = self.layout_stack.last() && *parent_depth == self.parent_stack.len() {
+            let region = self.parent_stack.last().map_or([0.0, 0.0, 1.0, 1.0], |&(_, parent_rect)| parent_rect);
+            let natural_scale = element.get_relative_rect().map(|rect| [
+                (rect[2] - rect[0]) / (region[2] - region[0]).max(f32::EPSILON),
+                (rect[3] - rect[1]) / (region[3] - region[1]).max(f32::EPSILON),
+            ]);
+            let container = &mut self.containers[*container];
+            let default_style = container.layout.default_item();
+            let style = styles.last()
+                .filter(|style| mem::discriminant(*style) == mem::discriminant(&default_style))
+                .cloned()
+                .unwrap_or(default_style);
+            container.items.push(LayoutChild {
+                id: element.get_id(),
+                style,
+                natural_scale,
+            });
+        }
+        element.set_clip_rect(self.clip_stack.last().copied());
+        element.set_camera(self.camera_stack.last().copied());
+        element.set_scale_factor(self.element_scale_factor(&element));
         self.elements.push(Box::new(element));
         self.id_iterator += 1;
+        self.rebuild_instances = true;
+    }
+
+    /// Derives the key of the next element from the order it was added in
+    /// within the innermost key scope.
+    fn next_key(&mut self) -> String {
+        let (scope_key, count) = self.key_scopes.last_mut().expect("The root key scope is never popped");
+        let key = if scope_key.is_empty() { count.to_string() } else { format!("{scope_key}/{count}") };
+        *count += 1;
+        key
+    }
+
+    /// Gives elements added from now on keys under `key`, nested in the
+    /// key scopes pushed before it.
+    pub(crate) fn push_key_scope(&mut self, key: &str) {
+        let (scope_key, _) = self.key_scopes.last().expect("The root key scope is never popped");
+        let key = if scope_key.is_empty() { key.to_string() } else { format!("{scope_key}/{key}") };
+        self.key_scopes.push((key, 0));
+    }
+
+    pub(crate) fn pop_key_scope(&mut self) {
+        if self.key_scopes.len() > 1 {
+            self.key_scopes.pop();
+        }
+    }
+
+    /// Adds elements from now on as children of the last element added, placed
+    /// in its rect and keyed under its key. Children of an element without a
+    /// rect of its own are placed in the rect it was placed in.
+    pub(crate) fn push_parent(&mut self) {
+        let parent = self.elements.last().expect("A parent is added before its children");
+        let parent_rect = parent.get_relative_rect()
+            .or_else(|| self.parent_stack.last().map(|&(_, parent_rect)| parent_rect))
+            .unwrap_or([0.0, 0.0, 1.0, 1.0]);
+        self.key_scopes.push((parent.get_key().to_string(), 0));
+        self.parent_stack.push((parent.get_id(), parent_rect));
+    }
+
+    pub(crate) fn pop_parent(&mut self) {
+        self.parent_stack.pop();
+        self.pop_key_scope();
+    }
+
+    /// Lays out elements added from now on with `layout`, in the rect of the
+    /// parent they're added in.
+    pub(crate) fn push_layout(&mut self, layout: Layout) {
+        self.containers.push(LayoutContainer {
+            layout,
+            parent: self.parent_stack.last().map(|&(parent_id, _)| parent_id),
+            camera: self.camera_stack.last().copied(),
+            items: Vec::new(),
+        });
+        self.layout_stack.push((self.containers.len() - 1, self.parent_stack.len(), Vec::new()));
+    }
+
+    pub(crate) fn pop_layout(&mut self) {
+        self.layout_stack.pop();
+    }
+
+    /// Sizes items added from now on in the innermost layout with `style`.
+    pub(crate) fn push_item_style(&mut self, style: ItemStyle) {
+        match self.layout_stack.last_mut() {
+            Some((container, _, styles)) => {
+                if mem::discriminant(&style) != mem::discriminant(&self.containers[*container].layout.default_item()) {
+                    log::warn!("{style:?} doesn't fit the layout it's in, it has no effect");
+                }
+                styles.push(style);
+            }
+            None => log::warn!("{style:?} added outside of a layout, it has no effect"),
+        }
+    }
+
+    pub(crate) fn pop_item_style(&mut self) {
+        if let Some((_, _, styles)) = self.layout_stack.last_mut() {
+            styles.pop();
+        }
+    }
+
+    /// Lays out the rows, columns and grids for the window size, and moves children
+    /// along with parents that were laid out. This runs before elements are
+    /// drawn or hit-tested, so layouts follow the window as it's resized.
+    pub fn update_layout(&mut self, window_size: [u32; 2]) {
+        if self.containers.is_empty() {
+            return;
+        }
+        let indices: HashMap<u32, usize> = self.elements.iter().enumerate().map(|(index, element)| (element.get_id(), index)).collect();
+        let window_rect = [0.0, 0.0, 1.0, 1.0];
+
+        // Parents come before their children, so one pass in the order elements
+        // were added places every parent before its children and items need it
+        for container in 0..self.containers.len() {
+            if self.containers[container].parent.is_none() {
+                self.layout_container(container, window_rect, window_size, &indices);
+            }
+        }
+        let mut regions: HashMap<u32, [f32; 4]> = HashMap::new();
+        for index in 0..self.elements.len() {
+            let id = self.elements[index].get_id();
+            let parent_region = self.parent_of(id).and_then(|parent| regions.get(&parent)).copied();
+            if let Some(parent_region) = parent_region {
+                self.elements[index].set_parent_rect(parent_region);
+            }
+            let region = self.elements[index].get_relative_rect().or(parent_region).unwrap_or(window_rect);
+            regions.insert(id, region);
+
+            for container in 0..self.containers.len() {
+                if self.containers[container].parent == Some(id) {
+                    self.layout_container(container, region, window_size, &indices);
+                }
+            }
+        }
+    }
+
+    /// Places a container's items in `region`, the rect of its parent relative to the window.
+    fn layout_container(&mut self, container: usize, region: [f32; 4], window_size: [u32; 2], indices: &HashMap<u32, usize>) {
+        // Canvas items are laid out in logical pixels, the same as their positions
+        let (space_size, pixel_scale) = match self.containers[container].camera {
+            Some(_) => (window_size.map(|size| (size as f32 / self.ui_scale()).round() as u32), 1.0),
+            None => (window_size, self.ui_scale()),
+        };
+        let region_pixels = [
+            region[0] * space_size[0] as f32,
+            region[1] * space_size[1] as f32,
+            region[2] * space_size[0] as f32,
+            region[3] * space_size[1] as f32,
+        ];
+
+        let mut item_indices = Vec::new();
+        let mut items = Vec::new();
+        for child in &self.containers[container].items {
+            let Some(&index) = indices.get(&child.id) else {
+                continue;
+            };
+            let natural = match child.natural_scale {
+                Some(scale) => [scale[0] * (region_pixels[2] - region_pixels[0]), scale[1] * (region_pixels[3] - region_pixels[1])],
+                None => self.elements[index].get_scale(space_size),
+            };
+            item_indices.push(index);
+            items.push((&child.style, natural));
+        }
+
+        let slots = self.containers[container].layout.layout(region_pixels, &items, pixel_scale);
+        for (index, slot) in item_indices.into_iter().zip(slots) {
+            self.elements[index].set_parent_rect(region);
+            self.elements[index].set_relative_rect([
+                slot[0] / space_size[0] as f32,
+                slot[1] / space_size[1] as f32,
+                slot[2] / space_size[0] as f32,
+                slot[3] / space_size[1] as f32,
+            ]);
+        }
+    }
+
+    pub fn parent_of(&self, id: u32) -> Option<u32> {
+        self.parents.get(&id).copied()
+    }
+
+    /// Returns the ids of the element's children in the order they were added.
+    pub fn children_of(&self, id: u32) -> Vec<u32> {
+        self.elements.iter()
+            .map(|element| element.get_id())
+            .filter(|child| self.parent_of(*child) == Some(id))
+            .collect()
+    }
+
+    /// Removes the element and everything added inside it, returns how
+    /// many elements were removed. The instance buffers need to be
+    /// updated afterwards.
+    pub fn remove_element(&mut self, id: u32) -> usize {
+        let mut removed = vec![id];
+        let mut next = 0;
+        while next < removed.len() {
+            for child in self.children_of(removed[next]) {
+                if !removed.contains(&child) {
+                    removed.push(child);
+                }
+            }
+            next += 1;
+        }
+
+        let element_count = self.elements.len();
+        self.elements.retain(|element| !removed.contains(&element.get_id()));
+        self.parents.retain(|child, parent| !removed.contains(child) && !removed.contains(parent));
+        self.rebuild_instances = true;
+        element_count - self.elements.len()
+    }
+
+    pub fn element_by_key(&mut self, key: &str) -> Option<&mut Box<dyn Element>> {
+        self.elements.iter_mut().find(|element| element.get_key() == key)
+    }
+
+    /// Carries state over from the interface this one was built to replace,
+    /// e.g. after rebuilding it for a new window size. Elements get the state
+    /// of the previous element with the same key and type, cameras keep the view
+    /// of the previous camera shown in a canvas with the same key, the scale
+    /// factor and zoom are kept, and the GPU buffers and text brushes are
+    /// reused, so the next update only writes what changed.
+    ///
+    /// Returns the ids of the previous elements mapped to the ids of the
+    /// elements replacing them, for ids held onto outside the interface.
+    pub fn reconcile(&mut self, mut previous: Interface) -> HashMap<u32, u32> {
+        let mut previous_elements: HashMap<String, PreviousElement> = previous.elements.iter()
+            .map(|element| (element.get_key().to_string(), (mem::discriminant(&element.get_element_type()), element.get_id(), element.get_state())))
+            .collect();
+        let mut ids = HashMap::new();
+        for element in &mut self.elements {
+            let Some((element_type, id, state)) = previous_elements.remove(element.get_key()) else {
+                continue;
+            };
+            // Keys follow the call site, a different kind of element there is a new one
+            if element_type != mem::discriminant(&element.get_element_type()) {
+                continue;
+            }
+            if let Some(state) = state {
+                element.set_state(state);
+            }
+            ids.insert(id, element.get_id());
+        }
+
+        let previous_cameras: HashMap<&str, usize> = previous.camera_keys.iter().enumerate()
+            .filter_map(|(index, key)| Some((key.as_deref()?, index)))
+            .collect();
+        previous.camera_bindings.resize_with(previous.cameras.len(), || None);
+        self.camera_bindings = self.camera_keys.iter().map(|_| None).collect();
+        for (index, key) in self.camera_keys.iter().enumerate() {
+            let Some(&previous_index) = key.as_deref().and_then(|key| previous_cameras.get(key)) else {
+                continue;
+            };
+            let previous_camera = &previous.cameras[previous_index];
+            self.cameras[index].set_position(previous_camera.position());
+            self.cameras[index].set_zoom(previous_camera.zoom());
+            self.camera_bindings[index] = previous.camera_bindings[previous_index].take();
+        }
+        self.scale_factor = previous.scale_factor;
+        self.zoom = previous.zoom;
+        self.apply_ui_scale();
+        self.translucent_materials = mem::take(&mut previous.translucent_materials);
+
+        self.vertex_buffers = mem::take(&mut previous.vertex_buffers);
+        self.index_buffers = mem::take(&mut previous.index_buffers);
+        // The previous instances are what the buffers hold, so they're diffed against
+        self.instance_buffers = mem::take(&mut previous.instance_buffers);
+        self.instances = mem::take(&mut previous.instances);
+        self.brush = previous.brush.take();
+        self.clipped_brushes = mem::take(&mut previous.clipped_brushes);
+        self.text_groups = mem::take(&mut previous.text_groups);
+        self.text_layers = mem::take(&mut previous.text_layers);
+        self.text_format = previous.text_format;
+        self.sample_count = previous.sample_count;
+        ids
+    }
+
+    /// Sets whether elements drawn with `material` are translucent, done by
+    /// [RenderState::register_material](crate::RenderState::register_material).
+    pub(crate) fn set_material_translucency(&mut self, material: &str, translucent: bool) {
+        if translucent {
+            self.translucent_materials.insert(material.to_string());
+        } else {
+            self.translucent_materials.remove(material);
+        }
+        self.rebuild_instances = true;
+    }
+
+    /// Whether the text brush was created, by [Interface::initalize_text_brush]
+    /// or taken over from a previous interface.
+    pub fn has_text_brush(&self) -> bool {
+        self.brush.is_some()
+    }
+
+    /// Clips elements added from now on to `relative_clip_rect`, as well as
+    /// any clip rects pushed before it.
+    pub(crate) fn push_clip_rect(&mut self, relative_clip_rect: [f32; 4]) {
+        let clip_rect = match self.clip_stack.last() {
+            Some(outer) => [
+                relative_clip_rect[0].max(outer[0]),
+                relative_clip_rect[1].max(outer[1]),
+                relative_clip_rect[2].min(outer[2]),
+                relative_clip_rect[3].min(outer[3]),
+            ],
+            None => relative_clip_rect,
+        };
+        self.clip_stack.push(clip_rect);
+    }
+
+    pub(crate) fn pop_clip_rect(&mut self) {
+        self.clip_stack.pop();
+    }
+
+    /// Sets the scale factor of the display the window is on, from
+    /// `Window::scale_factor` or a `ScaleFactorChanged` event. Text and style
+    /// sizes are in logical pixels and get multiplied by it and the zoom,
+    /// the instance buffers need to be rebuilt afterwards.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+        self.apply_ui_scale();
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Zooms the whole interface, canvases included, on top of the scale factor.
+    /// The instance buffers need to be rebuilt afterwards.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.max(f32::EPSILON);
+        self.apply_ui_scale();
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Physical pixels per logical pixel, the scale factor times the zoom.
+    pub fn ui_scale(&self) -> f32 {
+        self.scale_factor * self.zoom
+    }
+
+    fn apply_ui_scale(&mut self) {
+        let ui_scale = self.ui_scale();
+        self.rebuild_instances = true;
+        for camera in &mut self.cameras {
+            camera.set_scale_factor(ui_scale);
+        }
+        for index in 0..self.elements.len() {
+            let scale_factor = self.element_scale_factor(self.elements[index].as_ref());
+            self.elements[index].set_scale_factor(scale_factor);
+        }
+    }
+
+    /// Canvas cameras already scale everything they draw, so their elements
+    /// work in logical pixels while the rest work in physical ones.
+    fn element_scale_factor(&self, element: &dyn Element) -> f32 {
+        if element.get_camera().is_some() { 1.0 } else { self.ui_scale() }
+    }
+
+    /// The window size in the pixels each element's position and scale are in,
+    /// logical pixels in a canvas and physical ones elsewhere. Clip rects stay
+    /// in physical pixels either way.
+    fn element_window_sizes(&self, window_size: [u32; 2]) -> Vec<[u32; 2]> {
+        self.elements.iter().map(|element| self.element_window_size(element.as_ref(), window_size)).collect()
+    }
+
+    fn element_window_size(&self, element: &dyn Element, window_size: [u32; 2]) -> [u32; 2] {
+        let scale_factor = self.ui_scale() / self.element_scale_factor(element);
+        window_size.map(|size| (size as f32 / scale_factor).round() as u32)
+    }
+
+    /// Adds a camera canvases can show their elements through. Its screen
+    /// size and scale factor follow the window, so only the position and zoom
+    /// matter here.
+    pub fn add_camera(&mut self, mut camera: Camera2D) -> CameraHandle {
+        camera.set_scale_factor(self.ui_scale());
+        self.cameras.push(camera);
+        self.camera_keys.push(None);
+        CameraHandle(self.cameras.len() - 1)
+    }
+
+    pub fn camera(&self, handle: CameraHandle) -> Option<&Camera2D> {
+        self.cameras.get(handle.0)
+    }
+
+    /// Changes to the camera show up on the next frame, the instance
+    /// buffers don't need to be rebuilt.
+    pub fn camera_mut(&mut self, handle: CameraHandle) -> Option<&mut Camera2D> {
+        self.cameras.get_mut(handle.0)
+    }
+
+    /// Shows elements added from now on through `handle`. The canvas takes
+    /// the next key in its scope, like an element would.
+    pub(crate) fn push_camera(&mut self, handle: CameraHandle) {
+        let key = self.next_key();
+        if let Some(camera_key @ None) = self.camera_keys.get_mut(handle.0) {
+            *camera_key = Some(key);
+        }
+        self.camera_stack.push(handle);
+    }
+
+    pub(crate) fn pop_camera(&mut self) {
+        self.camera_stack.pop();
+    }
+
+    /// Writes the view of every camera into its uniform buffer, and into
+    /// the text brushes drawing through it. Bindings are created here rather
+    /// than with the instance buffers, so an interface that replaced another
+    /// one still gets them before its first frame.
+    pub(crate) fn update_cameras(&mut self, device: &Device, queue: &Queue, layout: &wgpu::BindGroupLayout, window_size: [u32; 2]) {
+        self.camera_bindings.resize_with(self.cameras.len(), || None);
+        for camera_binding in self.camera_bindings.iter_mut().filter(|binding| binding.is_none()) {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Canvas Camera Uniform Buffer"),
+                size: std::mem::size_of::<Camera2DUniform>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Canvas Camera Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }
+                ]
+            });
+            *camera_binding = Some((buffer, bind_group));
+        }
+
+        for (camera, (buffer, _)) in self.cameras.iter_mut().zip(self.camera_bindings.iter().flatten()) {
+            camera.update_screen_size(PhysicalSize::new(window_size[0], window_size[1]));
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[Camera2DUniform {
+                view_proj: camera.build_view_projection_matrix().to_cols_array_2d(),
+            }]));
+        }
+
+        for (brush, (_, _, camera)) in self.clipped_brushes.iter().zip(&self.text_groups) {
+            if let Some(camera) = camera.and_then(|handle| self.cameras.get(handle.0)) {
+                let projection = Mat4::from_cols_array_2d(&wgpu_text::ortho(window_size[0] as f32, window_size[1] as f32));
+                brush.update_matrix((projection * camera.build_view_matrix()).to_cols_array_2d(), queue);
+            }
+        }
+    }
+
+    /// Indices of the elements from back to front, sorted by z-index
+    /// and then by the order they were added in.
+    pub fn draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+        order.sort_by_key(|&index| self.elements[index].get_z_index());
+        order
+    }
+
+    /// Gives every element its own depth, following [Interface::draw_order].
+    fn element_depths(&self) -> Vec<f32> {
+        let mut depths = vec![0.0; self.elements.len()];
+        let step = 1.0 / (self.elements.len() + 1) as f32;
+        for (rank, index) in self.draw_order().into_iter().enumerate() {
+            depths[index] = 1.0 - (rank + 1) as f32 * step;
+        }
+        depths
+    }
+
+    /// Whether the element's own quad is translucent, label quads are never drawn.
+    fn is_translucent(&self, element: &dyn Element) -> bool {
+        if element.get_geometry_type() == GeometryType::Label {
+            return false;
+        }
+        match element.get_material() {
+            Some(material) => self.translucent_materials.contains(&material),
+            None => element.get_highlight().unwrap_or(element.get_color()[3]) < 1.0,
+        }
+    }
+
+    /// The layer each element's translucent quads and text are drawn in,
+    /// see [DrawCall]. `draw_order` is [Interface::draw_order].
+    fn draw_layers(&self, draw_order: &[usize]) -> Vec<usize> {
+        let mut layers = vec![0; self.elements.len()];
+        let mut layer = 0;
+        let mut text_in_layer = false;
+        for &index in draw_order {
+            let element = self.elements[index].as_ref();
+            let translucent = element.get_style().shadow.is_some() || self.is_translucent(element);
+            if translucent && text_in_layer {
+                layer += 1;
+                text_in_layer = false;
+            }
+            if element.get_geometry_type() == GeometryType::Label {
+                text_in_layer = true;
+            }
+            layers[index] = layer;
+        }
+        layers
+    }
+
+    /// Whether the cursor is inside the rect of every ancestor of each element,
+    /// ancestors without a rect don't restrict their children.
+    fn within_ancestors(&self, cursor_position: [f32; 2], element_window_sizes: &[[u32; 2]]) -> Vec<bool> {
+        let within_rects: Vec<bool> = self.elements.iter().zip(element_window_sizes).map(|(element, window_size)| {
+            let cursor_position = match element.get_camera().and_then(|handle| self.cameras.get(handle.0)) {
+                Some(camera) => camera.screen_to_world(cursor_position),
+                None => cursor_position,
+            };
+            element.get_relative_rect().is_none_or(|rect| {
+                cursor_position[0] >= rect[0] * window_size[0] as f32 && cursor_position[0] <= rect[2] * window_size[0] as f32
+                    && cursor_position[1] >= rect[1] * window_size[1] as f32 && cursor_position[1] <= rect[3] * window_size[1] as f32
+            })
+        }).collect();
+        let indices: HashMap<u32, usize> = self.elements.iter().enumerate().map(|(index, element)| (element.get_id(), index)).collect();
+
+        self.elements.iter().map(|element| {
+            let mut parent = self.parent_of(element.get_id());
+            // Bounded by the element count in case reused ids made a cycle
+            for _ in 0..self.elements.len() {
+                let Some(parent_index) = parent.and_then(|id| indices.get(&id)) else {
+                    return true;
+                };
+                if !within_rects[*parent_index] {
+                    return false;
+                }
+                parent = self.parent_of(self.elements[*parent_index].get_id());
+            }
+            true
+        }).collect()
+    }
+
+    /// Returns the topmost element under the cursor, elements
+    /// that don't take input never block the ones below them.
+    /// Children only take input inside their parents.
+    pub fn element_at(&mut self, cursor_position: [f32; 2], window_size: [u32; 2]) -> Option<&mut Box<dyn Element>> {
+        self.update_layout(window_size);
+        let draw_order = self.draw_order();
+        let element_window_sizes = self.element_window_sizes(window_size);
+        let within_ancestors = self.within_ancestors(cursor_position, &element_window_sizes);
+        let cameras = &self.cameras;
+        let topmost = draw_order.into_iter().rev().filter(|&index| within_ancestors[index]).find(|&index| {
+            let element = &mut self.elements[index];
+            let element_position = element.get_position(element_window_sizes[index]);
+            let element_scale = element.get_scale(element_window_sizes[index]);
+            // Clipped away parts of an element can't be clicked
+            let within_clip_rect = element.get_clip_rect(window_size).is_none_or(|clip_rect| {
+                cursor_position[0] >= clip_rect[0] && cursor_position[0] < clip_rect[2]
+                    && cursor_position[1] >= clip_rect[1] && cursor_position[1] < clip_rect[3]
+            });
+            // Elements seen through a camera are tested in its world space
+            let cursor_position = match element.get_camera().and_then(|handle| cameras.get(handle.0)) {
+                Some(camera) => camera.screen_to_world(cursor_position),
+                None => cursor_position,
+            };
+            within_clip_rect && element.is_cursor_within_bounds(cursor_position, element_position, element_scale)
+        })?;
+        Some(&mut self.elements[topmost])
     }
 
     pub fn geometry_vertices(geometry_type: &GeometryType) -> (Vec<Vertex>, Vec<u16>) {
@@ -103,147 +814,567 @@ impl Interface {
     }
 
     pub fn initalize_text_brush(&mut self, device: &Device, config: &wgpu::SurfaceConfiguration, queue: &Queue) {
-        let font_bytes = include_bytes!("../../../ComicMono.ttf");
-        self.brush = Some(BrushBuilder::using_font_bytes(font_bytes)
-            .unwrap()
-            .build(device, config.width, config.height, config.format));
+        self.text_format = config.format;
+        self.brush = Some(self.build_text_brush(device, [config.width, config.height]));
+        // Rebuilt on the next update in case the format or sample count changed
+        self.clipped_brushes.clear();
+        self.text_groups.clear();
 
-        let mut section = Vec::new();
-        
-        section.push(Section::default()
-            .add_text(Text::new("abcdefghijklmnopqrstuvwyxz")));
+        let section = vec![Section::default()
+            .add_text(Text::new("abcdefghijklmnopqrstuvwyxz"))];
 
         self.brush.as_mut().unwrap().queue(device, queue, section).expect("uh oh");
     }
 
+    fn build_text_brush(&self, device: &Device, window_size: [u32; 2]) -> TextBrush<FontRef<'static>> {
+        let font_bytes = include_bytes!("../../../ComicMono.ttf");
+        // Text is layered against quads but doesn't write depth, glyph
+        // quads would otherwise hide overlapping text behind them
+        BrushBuilder::using_font_bytes(font_bytes)
+            .unwrap()
+            .with_depth_stencil(Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }))
+            .with_multisample(wgpu::MultisampleState {
+                count: self.sample_count,
+                ..Default::default()
+            })
+            .build(device, window_size[0], window_size[1], self.text_format)
+    }
+
+    /// Matches the text brush to a new MSAA sample count or target format,
+    /// rebuilding it if it was already initialized.
+    pub(crate) fn set_text_target(&mut self, sample_count: u32, device: &Device, config: &wgpu::SurfaceConfiguration, queue: &Queue, window_size: [u32; 2]) {
+        self.sample_count = sample_count;
+        if self.brush.is_some() {
+            self.initalize_text_brush(device, config, queue);
+            self.update_text(device, queue, window_size);
+        }
+    }
+
+    /// Writes the elements' instances and text, see [Interface::update_instances].
     pub fn initialize_interface_buffers(&mut self, device: &Device, queue: &Queue, window_size: [u32; 2]) {
-        let mut batched_instances: HashMap<GeometryType, Vec<InstanceRaw>> = HashMap::new();
-        let atlas = &self.atlas;
-        
-        for element in &mut self.elements {
-            let atlas_entry = atlas.clone().get_entry_by_name(element.get_texture_name().unwrap_or("solid".to_string())).unwrap();
-            let tex_coords = [
-                atlas_entry.start_coord.unwrap().0,
-                atlas_entry.start_coord.unwrap().1,
-                atlas_entry.end_coord.unwrap().0,
-                atlas_entry.end_coord.unwrap().1,
-            ];
+        self.update_instances(device, queue, window_size);
+        self.resize_text_views(queue, window_size);
+        self.update_text(device, queue, window_size);
+    }
 
-            let instance = Instance::new(element.get_id(), element.get_geometry_type(), element.get_position(window_size), element.get_color(), element.get_scale(window_size));
-            let mut raw_instances = instance.to_raw();
-            raw_instances.tex_coords = tex_coords;
-            batched_instances
-                .entry(element.get_geometry_type())
-                .or_insert(Vec::new())
-                .push(raw_instances);
+    /// Writes the instances of the elements that changed since the last update,
+    /// see [Element::is_dirty], so hovering over a large interface stays cheap.
+    /// Adding, removing or rescaling elements, or resizing the window, rebuilds
+    /// every instance instead and writes the ones that differ from what the
+    /// buffers hold. Buffers are kept between updates and only recreated when a
+    /// batch outgrows its buffer. Text isn't updated, see [Interface::update_text],
+    /// unless it has to be drawn in another layer, see [DrawCall].
+    ///
+    /// Returns how many instances were written.
+    pub fn update_instances(&mut self, device: &Device, queue: &Queue, window_size: [u32; 2]) -> usize {
+        let slots_valid = !self.rebuild_instances
+            && window_size == self.instances_window_size
+            && self.element_slots.len() == self.elements.len();
+        if slots_valid && let Some(written) = self.update_dirty_instances(device, queue, window_size) {
+            return written;
         }
+        self.rebuild_all_instances(device, queue, window_size)
+    }
 
-        for (geometry_type, instances) in batched_instances.iter() {
-            let (vertices, indices) = Self::geometry_vertices(geometry_type);
+    /// Rebuilds and writes only the dirty elements' slots. Returns None without
+    /// writing anything if one of them has to move to another slot.
+    fn update_dirty_instances(&mut self, device: &Device, queue: &Queue, window_size: [u32; 2]) -> Option<usize> {
+        let dirty: Vec<usize> = (0..self.elements.len()).filter(|&index| self.elements[index].is_dirty()).collect();
+        for &index in &dirty {
+            let element = self.elements[index].as_ref();
+            let slots = &self.element_slots[index];
+            if Self::batch_key(element) != slots.batch_key || element.get_style().shadow.is_some() != slots.shadow_slot.is_some() {
+                return None;
+            }
+        }
 
-            let vertex_buffer_size = (vertices.len() * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
-            let index_buffer_size = (indices.len() * std::mem::size_of::<u16>()) as wgpu::BufferAddress;
-            let instance_buffer_size = (instances.len() * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress;
+        let shadow_depth_offset = self.shadow_depth_offset();
+        let mut written = 0;
+        let mut translucency_changed = false;
+        for &index in &dirty {
+            let (shadow, instance) = self.element_instances(index, self.element_slots[index].depth, shadow_depth_offset, window_size);
+            let translucent = self.is_translucent(self.elements[index].as_ref());
+            let slots = &mut self.element_slots[index];
+            translucency_changed |= slots.translucent != translucent;
+            slots.translucent = translucent;
 
-            self.vertex_buffers.insert(*geometry_type, device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Vertex Buffer"),
-                size: vertex_buffer_size,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false
-            }));
+            let (batch_key, slot, shadow_slot) = (slots.batch_key.clone(), slots.slot, slots.shadow_slot);
+            if let (Some(shadow), Some(shadow_slot)) = (shadow, shadow_slot) {
+                written += self.write_instance(queue, &Self::shadow_batch_key(batch_key.camera), shadow_slot, shadow);
+            }
+            written += self.write_instance(queue, &batch_key, slot, instance);
+            self.elements[index].clear_dirty();
+        }
 
-            self.index_buffers.insert(*geometry_type, device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Index Buffer"),
-                size: index_buffer_size,
-                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false
-            }));
+        // Quads turning translucent or opaque are drawn in other runs, and can move text to another layer
+        if translucency_changed {
+            let draw_order = self.draw_order();
+            let layers = self.draw_layers(&draw_order);
+            self.draw_calls = self.build_draw_calls(&draw_order, &layers);
+            if self.brush.is_some() && layers != self.text_layers {
+                self.update_text(device, queue, window_size);
+            }
+        }
+        Some(written)
+    }
 
-            self.instance_buffers.insert(*geometry_type, device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Instance Buffer"),
-                size: instance_buffer_size,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false
-            }));
+    /// Writes `instance` into its slot if it isn't there already, returns how many instances were written.
+    fn write_instance(&mut self, queue: &Queue, batch_key: &BatchKey, slot: usize, instance: InstanceRaw) -> usize {
+        let Some(previous) = self.instances.get_mut(batch_key).and_then(|instances| instances.get_mut(slot)) else {
+            return 0;
+        };
+        if *previous == instance {
+            return 0;
+        }
+        *previous = instance;
+        let instance_size = std::mem::size_of::<InstanceRaw>();
+        queue.write_buffer(&self.instance_buffers[batch_key], (slot * instance_size) as wgpu::BufferAddress, bytemuck::cast_slice(&[instance]));
+        1
+    }
+
+    /// Lays the elements out again and rebuilds every instance, writing the ones
+    /// that differ from what the buffers hold. Buffers of batches that are gone
+    /// are dropped.
+    fn rebuild_all_instances(&mut self, device: &Device, queue: &Queue, window_size: [u32; 2]) -> usize {
+        self.update_layout(window_size);
+        let (batched_instances, element_slots) = self.build_instances(window_size);
+        let instance_size = std::mem::size_of::<InstanceRaw>();
+        let mut written = 0;
+
+        for (batch_key, instances) in &batched_instances {
+            let geometry_type = batch_key.geometry_type;
+            // Quad geometry never changes, it's uploaded once
+            if let Entry::Vacant(vertex_entry) = self.vertex_buffers.entry(geometry_type) {
+                let (vertices, indices) = Self::geometry_vertices(&geometry_type);
+                vertex_entry.insert(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                }));
+                self.index_buffers.insert(geometry_type, device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Index Buffer"),
+                    contents: bytemuck::cast_slice(&indices),
+                    usage: wgpu::BufferUsages::INDEX,
+                }));
+            }
+
+            let required_size = (instances.len() * instance_size) as wgpu::BufferAddress;
+            let fits = self.instance_buffers.get(batch_key).is_some_and(|buffer| buffer.size() >= required_size);
+            if !fits {
+                // Grown to the next power of two so adding elements one by one doesn't recreate it every time
+                self.instance_buffers.insert(batch_key.clone(), device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Instance Buffer"),
+                    size: (instances.len().next_power_of_two() * instance_size) as wgpu::BufferAddress,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false
+                }));
+            }
+            let instance_buffer = &self.instance_buffers[batch_key];
+
+            let previous = self.instances.get(batch_key).filter(|_| fits);
+            let mut slot = 0;
+            while slot < instances.len() {
+                let changed = |slot: usize| previous.is_none_or(|previous| previous.get(slot) != Some(&instances[slot]));
+                if !changed(slot) {
+                    slot += 1;
+                    continue;
+                }
+                // Consecutive changed slots are written together
+                let run_start = slot;
+                while slot < instances.len() && changed(slot) {
+                    slot += 1;
+                }
+                queue.write_buffer(instance_buffer, (run_start * instance_size) as wgpu::BufferAddress, bytemuck::cast_slice(&instances[run_start..slot]));
+                written += slot - run_start;
+            }
         }
 
+        // Removed materials and canvases don't keep their buffers around
+        self.instance_buffers.retain(|batch_key, _| batched_instances.contains_key(batch_key));
         self.instances = batched_instances;
-        self.update_vertices(queue, window_size);
-        self.update_text(device, queue, window_size);
+        self.element_slots = element_slots;
+        for element in &mut self.elements {
+            element.clear_dirty();
+        }
+        self.rebuild_instances = false;
+        self.instances_window_size = window_size;
+
+        let draw_order = self.draw_order();
+        let layers = self.draw_layers(&draw_order);
+        self.draw_calls = self.build_draw_calls(&draw_order, &layers);
+        if self.brush.is_some() && layers != self.text_layers {
+            self.update_text(device, queue, window_size);
+        }
+        written
     }
 
-    pub fn update_vertices(&mut self, queue: &Queue, window_size: [u32; 2]) {
-        self.brush.as_ref().unwrap().resize_view(window_size[0] as f32, window_size[1] as f32, queue);
+    /// Builds every element's instances, batched in draw order, along with the
+    /// slots each element's instances ended up in.
+    fn build_instances(&mut self, window_size: [u32; 2]) -> (HashMap<BatchKey, Vec<InstanceRaw>>, Vec<ElementSlots>) {
+        let mut batched_instances: HashMap<BatchKey, Vec<InstanceRaw>> = HashMap::new();
+        let mut element_slots: Vec<Option<ElementSlots>> = (0..self.elements.len()).map(|_| None).collect();
+        let depths = self.element_depths();
+        let shadow_depth_offset = self.shadow_depth_offset();
+
+        let mut push_instance = |batch_key: BatchKey, raw_instance: InstanceRaw| {
+            let batch = batched_instances.entry(batch_key).or_default();
+            batch.push(raw_instance);
+            batch.len() - 1
+        };
+
+        for index in self.draw_order() {
+            let (shadow, instance) = self.element_instances(index, depths[index], shadow_depth_offset, window_size);
+            let element = self.elements[index].as_ref();
+            let batch_key = Self::batch_key(element);
+            let shadow_slot = shadow.map(|shadow| push_instance(Self::shadow_batch_key(batch_key.camera), shadow));
+            let slot = push_instance(batch_key.clone(), instance);
+            element_slots[index] = Some(ElementSlots {
+                batch_key,
+                slot,
+                shadow_slot,
+                depth: depths[index],
+                translucent: self.is_translucent(element),
+            });
+        }
+
+        (batched_instances, element_slots.into_iter().flatten().collect())
+    }
 
-        for (geometry_type, instances) in self.instances.iter() {
-            let (vertices, indices) = Self::geometry_vertices(geometry_type);
-            if let Some(vertex_buffer) = self.vertex_buffers.get(geometry_type) {
-                queue.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+    /// Runs of consecutive slots from the same batch and layer, back to front.
+    fn build_draw_calls(&self, draw_order: &[usize], layers: &[usize]) -> Vec<DrawCall> {
+        let mut draw_calls: Vec<DrawCall> = Vec::new();
+        let mut push_slot = |batch_key: BatchKey, slot: usize, layer: Option<usize>| {
+            let slot = slot as u32;
+            match draw_calls.last_mut() {
+                Some((last_key, range, last_layer)) if *last_key == batch_key && *last_layer == layer && range.end == slot => range.end += 1,
+                _ => draw_calls.push((batch_key, slot..slot + 1, layer)),
             }
-            if let Some(index_buffer) = self.index_buffers.get(geometry_type) {
-                queue.write_buffer(index_buffer, 0, bytemuck::cast_slice(&indices));
+        };
+
+        for &index in draw_order {
+            let slots = &self.element_slots[index];
+            if let Some(shadow_slot) = slots.shadow_slot {
+                push_slot(Self::shadow_batch_key(slots.batch_key.camera), shadow_slot, Some(layers[index]));
+            }
+            push_slot(slots.batch_key.clone(), slots.slot, slots.translucent.then_some(layers[index]));
+        }
+        draw_calls
+    }
+
+    fn batch_key(element: &dyn Element) -> BatchKey {
+        BatchKey { geometry_type: element.get_geometry_type(), material: element.get_material(), camera: element.get_camera() }
+    }
+
+    /// Shadows are drawn with the default pipeline through their element's camera.
+    fn shadow_batch_key(camera: Option<CameraHandle>) -> BatchKey {
+        BatchKey { geometry_type: GeometryType::Quad, material: None, camera }
+    }
+
+    /// Shadows sit halfway between their element and the one drawn before it.
+    fn shadow_depth_offset(&self) -> f32 {
+        0.5 / (self.elements.len() + 1) as f32
+    }
+
+    /// Builds the instance of an element and of its shadow, if it has one.
+    fn element_instances(&mut self, index: usize, depth: f32, shadow_depth_offset: f32, window_size: [u32; 2]) -> (Option<InstanceRaw>, InstanceRaw) {
+        let element_window_size = self.element_window_size(self.elements[index].as_ref(), window_size);
+        let ui_scale = self.ui_scale();
+        let atlas = &self.atlas;
+        let element = &mut self.elements[index];
+        let atlas_entry = atlas.get_entry_by_name(element.get_texture_name().unwrap_or("solid".to_string())).unwrap();
+        let tex_coords = [
+            atlas_entry.start_coord.unwrap().0,
+            atlas_entry.start_coord.unwrap().1,
+            atlas_entry.end_coord.unwrap().0,
+            atlas_entry.end_coord.unwrap().1,
+        ];
+
+        let scale = element.get_scale(element_window_size);
+        let transform = element.get_transform();
+        let pivot_offset = transform.pivot_offset(scale);
+        let element_position = element.get_position(element_window_size);
+        let position = [element_position[0] + pivot_offset[0], element_position[1] + pivot_offset[1]];
+        let clip_rect = element.get_clip_rect(window_size);
+        // Canvas cameras scale logical pixels themselves
+        let pixel_scale = if element.get_camera().is_some() { 1.0 } else { ui_scale };
+        let style = element.get_style().scaled(pixel_scale);
+
+        let raw_shadow = style.shadow.map(|shadow| {
+            let solid_entry = atlas.get_entry_by_name("solid".to_string()).unwrap();
+            let padding = 2.0 * (shadow.spread + shadow.blur_radius * SHADOW_BLUR_EXTENT);
+            let instance = Instance::new(
+                element.get_id(),
+                GeometryType::Quad,
+                [position[0] + shadow.offset[0], position[1] + shadow.offset[1]],
+                shadow.color,
+                [(scale[0] + padding).max(0.0), (scale[1] + padding).max(0.0)],
+            );
+            let mut raw_shadow = instance.to_raw();
+            raw_shadow.tex_coords = [
+                solid_entry.start_coord.unwrap().0,
+                solid_entry.start_coord.unwrap().1,
+                solid_entry.end_coord.unwrap().0,
+                solid_entry.end_coord.unwrap().1,
+            ];
+            raw_shadow.atlas_page = solid_entry.page;
+            raw_shadow.transform = transform.matrix();
+            raw_shadow.depth = depth + shadow_depth_offset;
+            // Square corners stay square however far the shadow spreads
+            let corner_radius = if style.corner_radius > 0.0 { (style.corner_radius + shadow.spread).max(0.0) } else { 0.0 };
+            raw_shadow.shape = [corner_radius, 0.0, shadow.blur_radius, 0.0];
+            if let Some(clip_rect) = clip_rect {
+                raw_shadow.clip_rect = clip_rect;
             }
-            if let Some(instance_buffer) = self.instance_buffers.get(geometry_type) {
+            raw_shadow
+        });
+
+        let mut color = element.get_color();
+        if let Some(alpha) = element.get_highlight() {
+            color[3] = alpha;
+        }
+        let instance = Instance::new(element.get_id(), element.get_geometry_type(), position, color, scale);
+        let mut raw_instances = instance.to_raw();
+        raw_instances.tex_coords = tex_coords;
+        raw_instances.atlas_page = atlas_entry.page;
+        raw_instances.transform = transform.matrix();
+        raw_instances.nine_slice = atlas_entry.nine_slice.unwrap_or_default().map(|inset| inset as f32);
+        raw_instances.depth = depth;
+        raw_instances.shape = [style.corner_radius, style.border_width, 0.0, pixel_scale];
+        raw_instances.border_color = style.border_color;
+        if let Some(clip_rect) = clip_rect {
+            raw_instances.clip_rect = clip_rect;
+        }
+        if let Some(gradient) = style.gradient {
+            (raw_instances.gradient_colors, raw_instances.gradient_offsets, raw_instances.gradient) = gradient.to_raw();
+        }
+
+        (raw_shadow, raw_instances)
+    }
+
+    /// Writes every instance again and resizes the text to the window.
+    pub fn update_vertices(&mut self, queue: &Queue, window_size: [u32; 2]) {
+        self.resize_text_views(queue, window_size);
+
+        for (batch_key, instances) in self.instances.iter() {
+            if let Some(instance_buffer) = self.instance_buffers.get(batch_key) {
                 queue.write_buffer(instance_buffer, 0, bytemuck::cast_slice(instances));
             }
         }
     }
 
+    fn resize_text_views(&self, queue: &Queue, window_size: [u32; 2]) {
+        if let Some(brush) = self.brush.as_ref() {
+            brush.resize_view(window_size[0] as f32, window_size[1] as f32, queue);
+        }
+        for brush in &self.clipped_brushes {
+            brush.resize_view(window_size[0] as f32, window_size[1] as f32, queue);
+        }
+    }
+
+    /// Queues the labels' text, does nothing until [Interface::initalize_text_brush] ran.
     pub fn update_text(&mut self, device: &Device, queue: &Queue, window_size: [u32; 2]) {
-        let mut label_data: Vec<(String, [f32; 4], Option<[f32; 2]>, [f32; 2])> = Vec::new();
-        for element in self.elements.iter_mut() {
+        if self.brush.is_none() {
+            return;
+        }
+        self.update_layout(window_size);
+        let mut label_data: Vec<LabelData> = Vec::new();
+        let depths = self.element_depths();
+        let element_window_sizes = self.element_window_sizes(window_size);
+        let draw_order = self.draw_order();
+        let layers = self.draw_layers(&draw_order);
+        // The brush expects sections from back to front
+        for index in draw_order {
+            let element = &mut self.elements[index];
             if element.get_geometry_type() == GeometryType::Label {
                 let text_ref = element.get_text().expect("Label element contained no text...");
-                label_data.push((
-                    text_ref.to_string(),
-                    element.get_color(),
-                    element.get_bounds(window_size),
-                    element.get_position(window_size)
-                ));
+                label_data.push(LabelData {
+                    text: text_ref.to_string(),
+                    color: element.get_color(),
+                    bounds: element.get_bounds(element_window_sizes[index]),
+                    position: element.get_position(element_window_sizes[index]),
+                    text_size: element.get_text_size().unwrap_or(DEFAULT_TEXT_SIZE),
+                    depth: depths[index],
+                    clip_rect: element.get_clip_rect(window_size),
+                    camera: element.get_camera(),
+                    layer: layers[index],
+                });
             }
         }
 
+        // Labels are grouped by layer, clip rect and camera, keeping them back to front within each group
         let mut sections: Vec<Section> = Vec::new();
+        let mut clipped_sections: Vec<(TextGroup, Vec<Section>)> = Vec::new();
         for data in &label_data {
             let mut section_builder = Section::builder()
-                .with_screen_position(data.3)
+                .with_screen_position(data.position)
                 .with_text(vec![
-                    Text::new(&data.0) 
-                        .with_scale(30.0)
-                        .with_color(data.1)
+                    Text::new(&data.text) 
+                        .with_scale(data.text_size)
+                        .with_color(data.color)
+                        .with_z(data.depth)
                 ]);
 
-            if data.2.is_some() {
-                section_builder = section_builder.with_bounds(data.2.unwrap());
+            if let Some(bounds) = data.bounds {
+                section_builder = section_builder.with_bounds(bounds);
+            }
+
+            if data.layer == 0 && data.clip_rect.is_none() && data.camera.is_none() {
+                sections.push(section_builder);
+                continue;
             }
-            sections.push(section_builder);
+            let scissor_rect = match data.clip_rect {
+                Some(clip_rect) => Self::scissor_rect(clip_rect, window_size),
+                None => [0, 0, window_size[0], window_size[1]],
+            };
+            let group_key = (data.layer, scissor_rect, data.camera);
+            match clipped_sections.iter_mut().find(|(key, _)| *key == group_key) {
+                Some((_, group)) => group.push(section_builder),
+                None => clipped_sections.push((group_key, vec![section_builder])),
+            }
+        }
+
+        self.text_layers = layers;
+
+        // Queued even when empty so the brush drops text from previous frames
+        self.brush.as_mut().unwrap().queue(device, queue, sections).unwrap();
+
+        while self.clipped_brushes.len() < clipped_sections.len() {
+            let brush = self.build_text_brush(device, window_size);
+            self.clipped_brushes.push(brush);
+        }
+        self.text_groups.clear();
+        for (brush, (group_key, group)) in self.clipped_brushes.iter_mut().zip(clipped_sections) {
+            brush.queue(device, queue, group).unwrap();
+            self.text_groups.push(group_key);
         }
-        
-        if !sections.is_empty() {
-            self.brush.as_mut().unwrap().queue(device, queue, sections).unwrap();
+        // Brushes reused for text outside any canvas drop the camera they had
+        for (brush, (_, _, camera)) in self.clipped_brushes.iter().zip(&self.text_groups) {
+            if camera.is_none() {
+                brush.resize_view(window_size[0] as f32, window_size[1] as f32, queue);
+            }
         }
     }
 
-    pub(crate)  fn draw_text_brush<'a>( &'a self, renderpass: &mut wgpu::RenderPass<'a>) {
-        if let Some(brush) = self.brush.as_ref() {
-            brush.draw(renderpass);
-        } else {
-            eprintln!("Warning: Brush not initialized for drawing.");
+    /// Converts a clip rect in pixels to a scissor rect inside the window. Pixels
+    /// are kept when their center is inside, matching how quads are clipped.
+    fn scissor_rect(clip_rect: [f32; 4], window_size: [u32; 2]) -> [u32; 4] {
+        let left = (clip_rect[0].round().max(0.0) as u32).min(window_size[0]);
+        let top = (clip_rect[1].round().max(0.0) as u32).min(window_size[1]);
+        let right = (clip_rect[2].round().max(0.0) as u32).clamp(left, window_size[0]);
+        let bottom = (clip_rect[3].round().max(0.0) as u32).clamp(top, window_size[1]);
+        [left, top, right - left, bottom - top]
+    }
+
+    /// Draws the text of one layer, see [DrawCall].
+    fn draw_text_layer<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, layer: usize, target_size: [u32; 2]) {
+        if layer == 0 {
+            if let Some(brush) = self.brush.as_ref() {
+                brush.draw(render_pass);
+            } else {
+                eprintln!("Warning: Brush not initialized for drawing.");
+            }
+        }
+
+        for (brush, (_, scissor_rect, _)) in self.clipped_brushes.iter().zip(&self.text_groups).filter(|(_, (group_layer, _, _))| *group_layer == layer) {
+            if scissor_rect[2] > 0 && scissor_rect[3] > 0 {
+                render_pass.set_scissor_rect(scissor_rect[0], scissor_rect[1], scissor_rect[2], scissor_rect[3]);
+                brush.draw(render_pass);
+                // Quads are clipped in the shader, they're drawn with the whole target
+                render_pass.set_scissor_rect(0, 0, target_size[0], target_size[1]);
+            }
         }
     }
 
-    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        for (geometry_type, instances) in self.instances.iter() {
-            let vertex_buffer = self.vertex_buffers.get(geometry_type).unwrap();
-            let index_buffer = self.index_buffers.get(geometry_type).unwrap();
-            let instance_buffer = self.instance_buffers.get(geometry_type).unwrap();
-            let (_vertices, indices) = Self::geometry_vertices(geometry_type);
+    /// Draws one run of instances with its material's pipeline, elements naming a
+    /// material that wasn't registered are drawn with the default one. Elements
+    /// in a canvas are drawn through its camera instead of `camera_bind_group`.
+    fn draw_instances<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, draw_call: &DrawCall, pipelines: &'a PipelineRegistry, camera_bind_group: &'a wgpu::BindGroup) {
+        let (batch_key, instance_range, layer) = draw_call;
+        let canvas_bind_group = batch_key.camera.and_then(|handle| self.camera_bindings.get(handle.0)?.as_ref());
+        render_pass.set_bind_group(0, canvas_bind_group.map_or(camera_bind_group, |(_, bind_group)| bind_group), &[]);
+
+        let geometry_type = &batch_key.geometry_type;
+        let vertex_buffer = self.vertex_buffers.get(geometry_type).unwrap();
+        let index_buffer = self.index_buffers.get(geometry_type).unwrap();
+        let instance_buffer = self.instance_buffers.get(batch_key).unwrap();
+        let (_vertices, indices) = Self::geometry_vertices(geometry_type);
 
-            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
-            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        let pipeline = match layer {
+            Some(_) => pipelines.get_translucent_or_default(batch_key.material.as_deref()),
+            None => pipelines.get_or_default(batch_key.material.as_deref()),
+        };
+        render_pass.set_pipeline(&pipeline.pipeline);
+        if let Some(bind_group) = &pipeline.bind_group {
+            render_pass.set_bind_group(2, bind_group, &[]);
+        }
+
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+        render_pass.draw_indexed(0..indices.len() as u32, 0, instance_range.clone());
+    }
 
-            render_pass.draw_indexed(0..indices.len() as u32, 0, 0..instances.len() as u32);
+    /// Draws the opaque instances, and then the translucent instances and
+    /// the text a layer at a time, see [DrawCall]. `atlas_bind_group` is
+    /// bound again after text since the brushes use their own bind groups.
+    pub(crate) fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipelines: &'a PipelineRegistry, camera_bind_group: &'a wgpu::BindGroup, atlas_bind_group: &'a wgpu::BindGroup, target_size: [u32; 2]) {
+        render_pass.set_bind_group(1, atlas_bind_group, &[]);
+        for draw_call in self.draw_calls.iter().filter(|(_, _, layer)| layer.is_none()) {
+            self.draw_instances(render_pass, draw_call, pipelines, camera_bind_group);
         }
+
+        let last_layer = self.draw_calls.iter().filter_map(|(_, _, layer)| *layer)
+            .chain(self.text_groups.iter().map(|(layer, _, _)| *layer))
+            .max()
+            .unwrap_or(0);
+        // Layers only grow along the draw order, so the runs are already sorted by layer
+        let mut translucent_calls = self.draw_calls.iter().filter(|(_, _, layer)| layer.is_some()).peekable();
+        for layer in 0..=last_layer {
+            render_pass.set_bind_group(1, atlas_bind_group, &[]);
+            while let Some(draw_call) = translucent_calls.next_if(|(_, _, call_layer)| *call_layer == Some(layer)) {
+                self.draw_instances(render_pass, draw_call, pipelines, camera_bind_group);
+            }
+            self.draw_text_layer(render_pass, layer, target_size);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use image::RgbaImage;
+    use winit::dpi::PhysicalSize;
+
+    use crate::{definitions::{UiAtlas, UiAtlasTexture}, user_interface::elements::Panel, RenderState};
+
+    use super::Interface;
+
+    #[test]
+    fn buffers_of_removed_batches_are_dropped() {
+        let mut atlas = UiAtlas::from_image(RgbaImage::new(8, 8));
+        atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 8, 8));
+        #[allow(clippy::arc_with_non_send_sync)]
+        let interface = Arc::new(Mutex::new(Interface::new(atlas)));
+        let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(8, 8), interface.clone())).unwrap();
+        let mut interface = interface.lock().unwrap();
+
+        interface.show(|ui| {
+            ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [1.0, 1.0], "solid"), Some(1));
+            ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [1.0, 1.0], "solid").with_material("glass"), Some(2));
+        });
+        interface.update_instances(&render_state.device, &render_state.queue, [8, 8]);
+        assert_eq!(interface.instance_buffers.len(), 2);
+
+        interface.remove_element(2);
+        interface.update_instances(&render_state.device, &render_state.queue, [8, 8]);
+        assert_eq!(interface.instance_buffers.len(), 1);
+        assert_eq!(interface.draw_calls.len(), 1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,498 @@
+/// The axis a row or column stacks its items along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Row,
+    Column,
+}
+
+/// Where items go along the main axis when they don't fill it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Start,
+    End,
+    Center,
+    /// The first and last items touch the edges, the rest is spread between them.
+    SpaceBetween,
+    /// Every item gets the same space on both sides.
+    SpaceAround,
+    /// The space between items and around the edges is the same.
+    SpaceEvenly,
+}
+
+/// Where items go along the cross axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    End,
+    Center,
+    /// Items fill the cross axis.
+    #[default]
+    Stretch,
+}
+
+/// Size of an item along the main axis before it grows or shrinks.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FlexBasis {
+    /// The scale the element was built with, relative to the parent the row
+    /// or column is in, or its size for elements sized in pixels.
+    #[default]
+    Auto,
+    /// Logical pixels.
+    Pixels(f32),
+    /// A fraction of the row or column, inside its padding.
+    Fraction(f32),
+}
+
+/// How a row or column spaces out its items, see [UserInterface::row](crate::user_interface::UserInterface::row).
+/// Gaps and padding are in logical pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Flex {
+    pub(crate) gap: f32,
+    pub(crate) padding: f32,
+    pub(crate) justify: Justify,
+    pub(crate) align: Align,
+}
+
+impl Flex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the space between neighbouring items.
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Sets the space kept clear around the edges.
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Lays out items with the given natural `[width, height]` in `rect`, all in
+    /// pixels with `[left, top, right, bottom]` rects. Lengths in logical pixels
+    /// are multiplied by `pixel_scale`.
+    pub(crate) fn layout(&self, direction: Direction, rect: [f32; 4], items: &[(FlexItem, [f32; 2])], pixel_scale: f32) -> Vec<[f32; 4]> {
+        let (main, cross) = match direction {
+            Direction::Row => (0, 1),
+            Direction::Column => (1, 0),
+        };
+        let padding = self.padding * pixel_scale;
+        let gap = self.gap * pixel_scale;
+        let content = [rect[0] + padding, rect[1] + padding, rect[2] - padding, rect[3] - padding];
+        let content_size = [(content[2] - content[0]).max(0.0), (content[3] - content[1]).max(0.0)];
+
+        let mut sizes: Vec<f32> = items.iter().map(|(item, natural)| match item.basis {
+            FlexBasis::Auto => natural[main],
+            FlexBasis::Pixels(pixels) => pixels * pixel_scale,
+            FlexBasis::Fraction(fraction) => fraction * content_size[main],
+        }.max(0.0)).collect();
+        let gaps = gap * items.len().saturating_sub(1) as f32;
+        let free = content_size[main] - sizes.iter().sum::<f32>() - gaps;
+
+        if free > 0.0 {
+            let total_grow: f32 = items.iter().map(|(item, _)| item.grow).sum();
+            if total_grow > 0.0 {
+                for (size, (item, _)) in sizes.iter_mut().zip(items) {
+                    *size += free * item.grow / total_grow;
+                }
+            }
+        } else if free < 0.0 {
+            // Larger items give up more of their size, like in CSS
+            let weights: Vec<f32> = sizes.iter().zip(items).map(|(size, (item, _))| item.shrink * size).collect();
+            let total_weight: f32 = weights.iter().sum();
+            if total_weight > 0.0 {
+                for (size, weight) in sizes.iter_mut().zip(weights) {
+                    *size = (*size + free * weight / total_weight).max(0.0);
+                }
+            }
+        }
+
+        let remaining = content_size[main] - sizes.iter().sum::<f32>() - gaps;
+        let spare = remaining.max(0.0);
+        let count = items.len() as f32;
+        let (offset, spacing) = match self.justify {
+            Justify::Start => (0.0, 0.0),
+            Justify::End => (remaining, 0.0),
+            Justify::Center => (remaining / 2.0, 0.0),
+            Justify::SpaceBetween if items.len() > 1 => (0.0, spare / (count - 1.0)),
+            Justify::SpaceBetween => (0.0, 0.0),
+            Justify::SpaceAround => (spare / count / 2.0, spare / count),
+            Justify::SpaceEvenly => (spare / (count + 1.0), spare / (count + 1.0)),
+        };
+
+        let mut position = content[main] + offset;
+        items.iter().zip(sizes).map(|((item, natural), size)| {
+            let align = item.align.unwrap_or(self.align);
+            let cross_size = if align == Align::Stretch { content_size[cross] } else { natural[cross] };
+            let cross_start = content[cross] + match align {
+                Align::Start | Align::Stretch => 0.0,
+                Align::End => content_size[cross] - cross_size,
+                Align::Center => (content_size[cross] - cross_size) / 2.0,
+            };
+
+            let mut slot = [0.0; 4];
+            slot[main] = position;
+            slot[main + 2] = position + size;
+            slot[cross] = cross_start;
+            slot[cross + 2] = cross_start + cross_size;
+            position += size + gap + spacing;
+            slot
+        }).collect()
+    }
+}
+
+/// How an element in a row or column is sized, see [UserInterface::flex_item](crate::user_interface::UserInterface::flex_item).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexItem {
+    pub(crate) grow: f32,
+    pub(crate) shrink: f32,
+    pub(crate) basis: FlexBasis,
+    pub(crate) align: Option<Align>,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self {
+            grow: 0.0,
+            shrink: 1.0,
+            basis: FlexBasis::Auto,
+            align: None,
+        }
+    }
+}
+
+impl FlexItem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the item's share of the space left over after every item got its basis.
+    pub fn with_grow(mut self, grow: f32) -> Self {
+        self.grow = grow;
+        self
+    }
+
+    /// Sets how much the item gives up, relative to its basis, when the
+    /// items don't fit. Zero keeps it at its basis.
+    pub fn with_shrink(mut self, shrink: f32) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    pub fn with_basis(mut self, basis: FlexBasis) -> Self {
+        self.basis = basis;
+        self
+    }
+
+    /// Aligns the item differently from the rest of the row or column.
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = Some(align);
+        self
+    }
+}
+
+/// Size of a grid row or column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    /// Logical pixels.
+    Pixels(f32),
+    /// A share of the space left after the other tracks, `Fraction(2.0)`
+    /// gets twice as much as `Fraction(1.0)`.
+    Fraction(f32),
+    /// Fits the largest item that sits only in this track, sized like an
+    /// item with [FlexBasis::Auto].
+    Auto,
+}
+
+/// How a grid sizes its tracks and places its items, see [UserInterface::grid](crate::user_interface::UserInterface::grid).
+/// Rows and columns beyond those given, from spans, areas or items that
+/// didn't fit, are [Track::Auto]. Gaps and padding are in logical pixels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Grid {
+    pub(crate) columns: Vec<Track>,
+    pub(crate) rows: Vec<Track>,
+    pub(crate) column_gap: f32,
+    pub(crate) row_gap: f32,
+    pub(crate) padding: f32,
+    /// Name, first column, first row, column span and row span of each area.
+    pub(crate) areas: Vec<(String, [usize; 4])>,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_columns(mut self, columns: &[Track]) -> Self {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    pub fn with_rows(mut self, rows: &[Track]) -> Self {
+        self.rows = rows.to_vec();
+        self
+    }
+
+    /// Sets the space between neighbouring columns and rows.
+    pub fn with_gap(mut self, gap: f32) -> Self {
+        self.column_gap = gap;
+        self.row_gap = gap;
+        self
+    }
+
+    /// Sets the space between neighbouring columns and between neighbouring rows separately.
+    pub fn with_gaps(mut self, column_gap: f32, row_gap: f32) -> Self {
+        self.column_gap = column_gap;
+        self.row_gap = row_gap;
+        self
+    }
+
+    /// Sets the space kept clear around the edges.
+    pub fn with_padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Names areas items can be placed in with [GridItem::area], one string per
+    /// row with a name per column, `.` leaves a cell unnamed. An area covers
+    /// every cell with its name, e.g. `["header header", "sidebar main"]`.
+    pub fn with_areas(mut self, rows: &[&str]) -> Self {
+        self.areas.clear();
+        for (row, names) in rows.iter().enumerate() {
+            for (column, name) in names.split_whitespace().enumerate() {
+                if name == "." {
+                    continue;
+                }
+                match self.areas.iter_mut().find(|(area_name, _)| area_name == name) {
+                    Some((_, [first_column, first_row, column_span, row_span])) => {
+                        let last_column = (*first_column + *column_span).max(column + 1);
+                        let last_row = (*first_row + *row_span).max(row + 1);
+                        *first_column = (*first_column).min(column);
+                        *first_row = (*first_row).min(row);
+                        *column_span = last_column - *first_column;
+                        *row_span = last_row - *first_row;
+                    }
+                    None => self.areas.push((name.to_string(), [column, row, 1, 1])),
+                }
+            }
+        }
+        self
+    }
+
+    /// Lays out items with the given natural `[width, height]` in `rect`, all in
+    /// pixels with `[left, top, right, bottom]` rects. Lengths in logical pixels
+    /// are multiplied by `pixel_scale`.
+    pub(crate) fn layout(&self, rect: [f32; 4], items: &[(GridItem, [f32; 2])], pixel_scale: f32) -> Vec<[f32; 4]> {
+        let padding = self.padding * pixel_scale;
+        let content = [rect[0] + padding, rect[1] + padding, rect[2] - padding, rect[3] - padding];
+        let cells = self.place(items);
+
+        let column_count = cells.iter().map(|cell| cell[0] + cell[2]).max().unwrap_or(0).max(self.columns.len());
+        let row_count = cells.iter().map(|cell| cell[1] + cell[3]).max().unwrap_or(0).max(self.rows.len());
+        let column_spans: Vec<(usize, usize, f32)> = cells.iter().zip(items).map(|(cell, (_, natural))| (cell[0], cell[2], natural[0])).collect();
+        let row_spans: Vec<(usize, usize, f32)> = cells.iter().zip(items).map(|(cell, (_, natural))| (cell[1], cell[3], natural[1])).collect();
+        let column_starts = track_starts(&self.columns, column_count, content[0], content[2], self.column_gap * pixel_scale, &column_spans, pixel_scale);
+        let row_starts = track_starts(&self.rows, row_count, content[1], content[3], self.row_gap * pixel_scale, &row_spans, pixel_scale);
+
+        cells.iter().map(|&[column, row, column_span, row_span]| [
+            column_starts[column].0,
+            row_starts[row].0,
+            column_starts[column + column_span - 1].1,
+            row_starts[row + row_span - 1].1,
+        ]).collect()
+    }
+
+    /// Picks the cells of every item as `[column, row, column span, row span]`.
+    /// Items with a cell or area are placed first, the rest fill the first
+    /// free cells row by row.
+    fn place(&self, items: &[(GridItem, [f32; 2])]) -> Vec<[usize; 4]> {
+        let area_columns = self.areas.iter().map(|(_, area)| area[0] + area[2]).max().unwrap_or(0);
+        let column_count = self.columns.len().max(area_columns).max(1);
+        let mut taken: Vec<[usize; 4]> = Vec::new();
+        let mut cells: Vec<Option<[usize; 4]>> = items.iter().map(|(item, _)| {
+            let cell = match &item.placement {
+                GridPlacement::Cell(column, row) => Some([*column, *row, item.column_span.max(1), item.row_span.max(1)]),
+                GridPlacement::Area(name) => {
+                    let area = self.areas.iter().find(|(area_name, _)| area_name == name).map(|(_, area)| *area);
+                    if area.is_none() {
+                        log::warn!("Grid area '{name}' doesn't exist, the item is placed automatically");
+                    }
+                    area
+                }
+                GridPlacement::Auto => None,
+            };
+            taken.extend(cell);
+            cell
+        }).collect();
+
+        let overlaps = |a: [usize; 4], b: [usize; 4]| {
+            a[0] < b[0] + b[2] && b[0] < a[0] + a[2] && a[1] < b[1] + b[3] && b[1] < a[1] + a[3]
+        };
+        for (cell, (item, _)) in cells.iter_mut().zip(items) {
+            if cell.is_some() {
+                continue;
+            }
+            let column_span = item.column_span.clamp(1, column_count);
+            let row_span = item.row_span.max(1);
+            let free = (0..).flat_map(|row| (0..=column_count - column_span).map(move |column| [column, row, column_span, row_span]))
+                .find(|candidate| taken.iter().all(|other| !overlaps(*candidate, *other)))
+                .expect("A grid always has free cells further down");
+            taken.push(free);
+            *cell = Some(free);
+        }
+        cells.into_iter().flatten().collect()
+    }
+}
+
+/// Sizes `count` tracks to fit between `start` and `end`, and returns where
+/// each one starts and ends. `spans` holds the first track, span and natural
+/// size of every item.
+fn track_starts(tracks: &[Track], count: usize, start: f32, end: f32, gap: f32, spans: &[(usize, usize, f32)], pixel_scale: f32) -> Vec<(f32, f32)> {
+    let track = |index: usize| tracks.get(index).copied().unwrap_or(Track::Auto);
+    let mut sizes: Vec<f32> = (0..count).map(|index| match track(index) {
+        Track::Pixels(pixels) => pixels * pixel_scale,
+        Track::Fraction(_) => 0.0,
+        Track::Auto => spans.iter()
+            .filter(|(first, span, _)| *first == index && *span == 1)
+            .map(|(_, _, natural)| *natural)
+            .fold(0.0, f32::max),
+    }).collect();
+
+    let gaps = gap * count.saturating_sub(1) as f32;
+    let free = ((end - start) - sizes.iter().sum::<f32>() - gaps).max(0.0);
+    let total_fraction: f32 = (0..count).filter_map(|index| match track(index) {
+        Track::Fraction(fraction) => Some(fraction),
+        _ => None,
+    }).sum();
+    if total_fraction > 0.0 {
+        for (index, size) in sizes.iter_mut().enumerate() {
+            if let Track::Fraction(fraction) = track(index) {
+                *size = free * fraction / total_fraction;
+            }
+        }
+    }
+
+    let mut position = start;
+    sizes.into_iter().map(|size| {
+        let track_start = position;
+        position += size + gap;
+        (track_start, track_start + size)
+    }).collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+enum GridPlacement {
+    #[default]
+    Auto,
+    /// Column and row.
+    Cell(usize, usize),
+    Area(String),
+}
+
+/// Where an element in a grid goes, see [UserInterface::grid_item](crate::user_interface::UserInterface::grid_item).
+/// Items fill the cells they cover.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridItem {
+    placement: GridPlacement,
+    column_span: usize,
+    row_span: usize,
+}
+
+impl Default for GridItem {
+    fn default() -> Self {
+        Self {
+            placement: GridPlacement::Auto,
+            column_span: 1,
+            row_span: 1,
+        }
+    }
+}
+
+impl GridItem {
+    /// Places the item in the first free cells, row by row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Places the item with its top left corner in a cell, counted from zero.
+    pub fn at(column: usize, row: usize) -> Self {
+        Self {
+            placement: GridPlacement::Cell(column, row),
+            ..Self::default()
+        }
+    }
+
+    /// Places the item in an area named with [Grid::with_areas], it covers
+    /// the whole area.
+    pub fn area(name: &str) -> Self {
+        Self {
+            placement: GridPlacement::Area(name.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets how many columns and rows the item covers.
+    pub fn with_span(mut self, columns: usize, rows: usize) -> Self {
+        self.column_span = columns;
+        self.row_span = rows;
+        self
+    }
+}
+
+/// How an element is sized in the container it's in.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ItemStyle {
+    Flex(FlexItem),
+    Grid(GridItem),
+}
+
+/// A layout container's rules, see [UserInterface::row](crate::user_interface::UserInterface::row)
+/// and [UserInterface::grid](crate::user_interface::UserInterface::grid).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Layout {
+    Flex(Direction, Flex),
+    Grid(Grid),
+}
+
+impl Layout {
+    /// The style of items that weren't given one.
+    pub(crate) fn default_item(&self) -> ItemStyle {
+        match self {
+            Layout::Flex(..) => ItemStyle::Flex(FlexItem::default()),
+            Layout::Grid(_) => ItemStyle::Grid(GridItem::default()),
+        }
+    }
+
+    /// Lays out items with the given styles and natural `[width, height]`, see [Flex::layout] and [Grid::layout].
+    pub(crate) fn layout(&self, rect: [f32; 4], items: &[(&ItemStyle, [f32; 2])], pixel_scale: f32) -> Vec<[f32; 4]> {
+        match self {
+            Layout::Flex(direction, flex) => {
+                let items: Vec<(FlexItem, [f32; 2])> = items.iter().map(|(style, natural)| match style {
+                    ItemStyle::Flex(item) => (*item, *natural),
+                    ItemStyle::Grid(_) => (FlexItem::default(), *natural),
+                }).collect();
+                flex.layout(*direction, rect, &items, pixel_scale)
+            }
+            Layout::Grid(grid) => {
+                let items: Vec<(GridItem, [f32; 2])> = items.iter().map(|(style, natural)| match style {
+                    ItemStyle::Grid(item) => (item.clone(), *natural),
+                    ItemStyle::Flex(_) => (GridItem::default(), *natural),
+                }).collect();
+                grid.layout(rect, &items, pixel_scale)
+            }
+        }
+    }
+}
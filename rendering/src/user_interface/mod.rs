@@ -1,13 +1,136 @@
-use crate::{definitions::{Color, ColorExt}, user_interface::{elements::{Button, Element, Icon, Label, Panel, TextBox, UiEvent}, interface::Interface}};
+use crate::{camera::CameraHandle, definitions::{Color, ColorExt}, user_interface::{elements::{Button, Element, Icon, Label, Panel, QuadStyle, TextBox, UiEvent}, interface::Interface, layout::{Direction, Flex, FlexItem, Grid, GridItem, ItemStyle, Layout}}};
 
 pub mod interface;
 pub mod elements;
+pub mod layout;
 
 pub struct UserInterface<'a> {
     interface: &'a mut Interface,
 }
 
 impl<'a> UserInterface<'a> {
+    /// Clips every element added in `elements_builder` to a rectangle with the
+    /// given center and scale, relative to the window like other elements.
+    /// Clips nest, so an element is only drawn where all of its clips overlap.
+    pub fn clip<R>(
+        &mut self,
+        relative_position: [f32; 2],
+        relative_scale: [f32; 2],
+        elements_builder: impl FnOnce(&mut UserInterface) -> R
+    ) -> R
+    {
+        self.interface.push_clip_rect([
+            relative_position[0] - relative_scale[0] / 2.0,
+            relative_position[1] - relative_scale[1] / 2.0,
+            relative_position[0] + relative_scale[0] / 2.0,
+            relative_position[1] + relative_scale[1] / 2.0,
+        ]);
+        let result = elements_builder(self);
+        self.interface.pop_clip_rect();
+        result
+    }
+
+    /// Shows every element added in `elements_builder` through `camera`, clipped
+    /// to a region of the window with the given center and scale. Positions in
+    /// the canvas are world positions, relative to the window at the camera's
+    /// default position and zoom, while nested clips stay relative to the window.
+    pub fn canvas<R>(
+        &mut self,
+        camera: CameraHandle,
+        relative_position: [f32; 2],
+        relative_scale: [f32; 2],
+        elements_builder: impl FnOnce(&mut UserInterface) -> R
+    ) -> R
+    {
+        self.interface.push_camera(camera);
+        let result = self.clip(relative_position, relative_scale, elements_builder);
+        self.interface.pop_camera();
+        result
+    }
+
+    /// Keys every element added in `elements_builder` under `key`, rather than
+    /// by the order they were added in among the elements around them. Their
+    /// state then survives a rebuild that adds or removes elements before them,
+    /// see [Interface::reconcile]. Keys need to be unique among their siblings.
+    pub fn keyed<R>(&mut self, key: &str, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.interface.push_key_scope(key);
+        let result = elements_builder(self);
+        self.interface.pop_key_scope();
+        result
+    }
+
+    /// Adds `element` with the elements from `children_builder` as its children.
+    /// Their positions and scales are relative to the parent's rectangle rather
+    /// than the window, they only take input inside it, and they're removed
+    /// along with it, see [Interface::remove_element].
+    pub fn parent<R>(
+        &mut self,
+        element: impl Element + 'static,
+        id: Option<u32>,
+        children_builder: impl FnOnce(&mut UserInterface) -> R
+    ) -> R
+    {
+        self.interface.add_elements(element, id);
+        self.interface.push_parent();
+        let result = children_builder(self);
+        self.interface.pop_parent();
+        result
+    }
+
+    /// Adds a panel with the elements from `children_builder` inside it,
+    /// see [UserInterface::parent].
+    pub fn panel<R>(&mut self, panel: Panel, id: Option<u32>, children_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.parent(panel, id, children_builder)
+    }
+
+    /// Lays out the elements added in `elements_builder` left to right across the
+    /// rect of the parent they're added in, or the window. Each element's basis
+    /// defaults to the scale it was built with, see [UserInterface::flex_item]
+    /// to size them otherwise. Layouts are redone whenever the window size changes.
+    pub fn row<R>(&mut self, flex: Flex, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.layout(Layout::Flex(Direction::Row, flex), elements_builder)
+    }
+
+    /// Lays out the elements added in `elements_builder` top to bottom, see [UserInterface::row].
+    pub fn column<R>(&mut self, flex: Flex, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.layout(Layout::Flex(Direction::Column, flex), elements_builder)
+    }
+
+    /// Lays out the elements added in `elements_builder` in a grid across the
+    /// rect of the parent they're added in, or the window. Elements fill their
+    /// cells, see [UserInterface::grid_item] to place them. Like rows, grids are
+    /// redone whenever the window size changes.
+    pub fn grid<R>(&mut self, grid: Grid, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.layout(Layout::Grid(grid), elements_builder)
+    }
+
+    fn layout<R>(&mut self, layout: Layout, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.interface.push_layout(layout);
+        let result = elements_builder(self);
+        self.interface.pop_layout();
+        result
+    }
+
+    /// Sizes the elements added in `elements_builder` with `item` in the row or
+    /// column they're in. Elements inside them are children, not items, so
+    /// they keep their own sizes.
+    pub fn flex_item<R>(&mut self, item: FlexItem, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.item_style(ItemStyle::Flex(item), elements_builder)
+    }
+
+    /// Places the elements added in `elements_builder` in the grid they're in
+    /// with `item`, see [UserInterface::flex_item].
+    pub fn grid_item<R>(&mut self, item: GridItem, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.item_style(ItemStyle::Grid(item), elements_builder)
+    }
+
+    fn item_style<R>(&mut self, style: ItemStyle, elements_builder: impl FnOnce(&mut UserInterface) -> R) -> R {
+        self.interface.push_item_style(style);
+        let result = elements_builder(self);
+        self.interface.pop_item_style();
+        result
+    }
+
     /// Used for adding a manually constructed element to the [Interface].
     pub fn add_element(&mut self, element: impl Element + 'static, id: Option<u32>) {
         self.interface.add_elements(element, id);
@@ -26,7 +149,23 @@ impl<'a> UserInterface<'a> {
         id: Option<u32>
     ) -> &mut Self
     {
-        let element = Panel::new(relative_position, Color::from_hex(color).into_vec4(), relative_scale, texture_name);
+        self.add_panel_styled(relative_position, color, relative_scale, texture_name, id, QuadStyle::default())
+    }
+
+    /// Adds a panel like [UserInterface::add_panel], with a style that
+    /// rounds its corners and gives it a border.
+    pub fn add_panel_styled(
+        &mut self, 
+        relative_position: [f32; 2], 
+        color: &str, 
+        relative_scale: [f32; 2], 
+        texture_name: &str,
+        id: Option<u32>,
+        style: QuadStyle
+    ) -> &mut Self
+    {
+        let element = Panel::new(relative_position, Color::from_hex(color).into_vec4(), relative_scale, texture_name)
+            .with_style(style);
         self.interface.add_elements(element, id);
         self
     }
@@ -42,9 +181,24 @@ impl<'a> UserInterface<'a> {
         on_click: Box<dyn Fn() + Send + Sync>, 
         texture_name: &str
     )
+    {
+        self.add_button_styled(relative_position, color, relative_scale, on_click, texture_name, QuadStyle::default());
+    }
+
+    /// Adds a button like [UserInterface::add_button], with a style.
+    pub fn add_button_styled(
+        &mut self, 
+        relative_position: [f32; 2], 
+        color: &str, 
+        relative_scale: [f32; 2], 
+        on_click: Box<dyn Fn() + Send + Sync>, 
+        texture_name: &str,
+        style: QuadStyle
+    )
     {
         let element = Button::new(relative_position, Color::from_hex(color).into_vec4(), relative_scale, texture_name)
-            .with_fn(on_click);
+            .with_fn(on_click)
+            .with_style(style);
         self.interface.add_elements(element, None);
     }
 
@@ -59,9 +213,24 @@ impl<'a> UserInterface<'a> {
         on_click: impl Fn() -> UiEvent + 'static, 
         texture_name: &str
     )
+    {
+        self.add_prop_button_styled(relative_position, color, relative_scale, on_click, texture_name, QuadStyle::default());
+    }
+
+    /// Adds a button like [UserInterface::add_prop_button], with a style.
+    pub fn add_prop_button_styled(
+        &mut self, 
+        relative_position: [f32; 2], 
+        color: &str, 
+        relative_scale: [f32; 2], 
+        on_click: impl Fn() -> UiEvent + 'static, 
+        texture_name: &str,
+        style: QuadStyle
+    )
     {
         let element = Button::new(relative_position, Color::from_hex(color).into_vec4(), relative_scale, texture_name)
-            .with_prop_fn(on_click);
+            .with_prop_fn(on_click)
+            .with_style(style);
         self.interface.add_elements(element, None);
     }
 
@@ -81,7 +250,8 @@ impl<'a> UserInterface<'a> {
 
     /// Used to add an icon, this is effectively
     /// a panel but rather than providing a relative scale
-    /// the size of the icon is defined by real pixels in f32 format.
+    /// the size of the icon is defined by logical pixels in f32 format,
+    /// so it follows the scale factor and zoom.
     pub fn add_icon(
         &mut self, 
         relative_position: [f32; 2], 
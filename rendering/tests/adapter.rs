@@ -0,0 +1,34 @@
+use std::sync::{Arc, Mutex};
+
+use rendering::{adapter::AdapterSelection, user_interface::interface::Interface, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+fn headless(adapter_selection: AdapterSelection) -> Result<RenderState, Box<dyn std::error::Error>> {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    pollster::block_on(RenderState::new_headless_with_adapter(PhysicalSize::new(8, 8), interface, adapter_selection))
+}
+
+#[test]
+fn adapters_can_be_picked_by_name() {
+    let default_adapter = headless(AdapterSelection::default()).unwrap().adapter_info();
+    assert!(!default_adapter.name.is_empty());
+
+    // Names match case-insensitively on any part of the adapter's name
+    let partial_name: String = default_adapter.name.chars().take(4).collect::<String>().to_uppercase();
+    let named = headless(AdapterSelection::new().with_name(Some(&partial_name))).unwrap();
+    assert!(named.adapter_info().name.to_uppercase().contains(&partial_name));
+
+    // An unknown name falls back to the power preference
+    assert!(headless(AdapterSelection::new().with_name(Some("no such adapter"))).is_ok());
+}
+
+#[test]
+fn selection_fails_without_a_matching_backend() {
+    let selection = AdapterSelection::new().with_backends(wgpu::Backends::empty()).with_software_fallback(false);
+    assert!(headless(selection).is_err());
+}
@@ -0,0 +1,70 @@
+use std::sync::{Arc, Mutex};
+
+use image::{Rgba, RgbaImage};
+use rendering::{definitions::{UiAtlas, UiAtlasTexture}, user_interface::interface::Interface, RenderState};
+use winit::dpi::PhysicalSize;
+
+fn render_panels(interface: &Arc<Mutex<Interface>>, render_state: &RenderState, textures: [&str; 2]) -> RgbaImage {
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            ui.add_panel([0.25, 0.5], "#ffffffff", [0.5, 1.0], textures[0], None);
+            ui.add_panel([0.75, 0.5], "#ffffffff", [0.5, 1.0], textures[1], None);
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [64, 64]);
+    }
+    render_state.read_frame().unwrap()
+}
+
+#[test]
+fn baked_pages_are_sampled_by_page() {
+    // Two 8x8 pages stacked vertically, white on the first and blue on the second
+    let mut image = RgbaImage::from_pixel(8, 16, Rgba([255, 255, 255, 255]));
+    for y in 8..16 {
+        for x in 0..8 {
+            image.put_pixel(x, y, Rgba([0, 0, 255, 255]));
+        }
+    }
+    let mut atlas = UiAtlas::from_pages(image, 2);
+    atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 8, 8));
+    atlas.add_entry(UiAtlasTexture::new("blue".to_string(), 0, 0, 8, 8).with_page(1));
+
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(atlas)));
+    let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(64, 64), interface.clone())).unwrap();
+
+    let frame = render_panels(&interface, &render_state, ["solid", "blue"]);
+    assert_eq!(frame.get_pixel(16, 32), &Rgba([255, 255, 255, 255]));
+    assert_eq!(frame.get_pixel(48, 32), &Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn full_pages_spill_onto_a_new_page() {
+    let image = RgbaImage::from_pixel(8, 8, Rgba([255, 255, 255, 255]));
+    let mut atlas = UiAtlas::from_image(image);
+    atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 8, 8));
+
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(atlas)));
+    let mut render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(64, 64), interface.clone())).unwrap();
+
+    // No two of these can share a 2048x2048 page
+    render_state.add_texture("red", &RgbaImage::from_pixel(1500, 1500, Rgba([255, 0, 0, 255]))).unwrap();
+    render_state.add_texture("green", &RgbaImage::from_pixel(1500, 1500, Rgba([0, 255, 0, 255]))).unwrap();
+    render_state.add_texture("blue", &RgbaImage::from_pixel(1500, 1500, Rgba([0, 0, 255, 255]))).unwrap();
+
+    {
+        let interface_guard = interface.lock().unwrap();
+        let atlas = interface_guard.atlas();
+        assert_eq!(atlas.pages(), 3);
+        let pages: Vec<u32> = ["red", "green", "blue"].iter()
+            .map(|name| atlas.get_entry_by_name(name.to_string()).unwrap().page)
+            .collect();
+        assert_eq!(pages, [0, 1, 2]);
+    }
+
+    let frame = render_panels(&interface, &render_state, ["red", "blue"]);
+    assert_eq!(frame.get_pixel(16, 32), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(48, 32), &Rgba([0, 0, 255, 255]));
+}
@@ -0,0 +1,112 @@
+use std::sync::{Arc, Mutex};
+
+use image::{Rgba, RgbaImage};
+use rendering::{camera::Camera2D, user_interface::{elements::{Button, Label, Panel}, interface::Interface}, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+fn assert_close(a: [f32; 2], b: [f32; 2]) {
+    assert!((a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4, "{a:?} != {b:?}");
+}
+
+fn zoomed_camera() -> Camera2D {
+    let mut camera = Camera2D::new(64, 64);
+    camera.set_position([16.0, 16.0]);
+    camera.set_zoom(2.0);
+    camera
+}
+
+#[test]
+fn screen_and_world_points_round_trip() {
+    let mut camera = zoomed_camera();
+    assert_close(camera.world_to_screen([24.0, 40.0]), [16.0, 48.0]);
+    assert_close(camera.screen_to_world(camera.world_to_screen([3.0, -7.5])), [3.0, -7.5]);
+
+    // Dragging 10 pixels moves the world 10 pixels with the cursor
+    let before = camera.world_to_screen([30.0, 30.0]);
+    camera.pan([10.0, -4.0]);
+    assert_close(camera.world_to_screen([30.0, 30.0]), [before[0] + 10.0, before[1] - 4.0]);
+}
+
+#[test]
+fn zooming_keeps_the_point_under_the_cursor() {
+    let mut camera = zoomed_camera();
+    let anchor = camera.screen_to_world([20.0, 50.0]);
+
+    camera.zoom_at(1.5, [20.0, 50.0]);
+    assert_eq!(camera.zoom(), 3.0);
+    assert_close(camera.screen_to_world([20.0, 50.0]), anchor);
+
+    camera.zoom_at(0.0, [20.0, 50.0]);
+    assert!(camera.zoom() > 0.0);
+}
+
+fn render_canvas(camera: Camera2D, builder: impl FnOnce(&mut rendering::user_interface::UserInterface)) -> RgbaImage {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(64, 64), interface.clone())).unwrap();
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        let camera = interface_guard.add_camera(camera);
+        interface_guard.show(|ui| {
+            ui.add_element(Panel::new([0.125, 0.125], BLUE, [0.25, 0.25], "solid"), None);
+            // Right half of the window
+            ui.canvas(camera, [0.75, 0.5], [0.5, 1.0], builder);
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [64, 64]);
+    }
+    render_state.read_frame().unwrap()
+}
+
+#[test]
+fn canvas_elements_are_drawn_through_its_camera() {
+    let frame = render_canvas(zoomed_camera(), |ui| {
+        // 24..40 in the world, 16..48 on screen
+        ui.add_element(Panel::new([0.5, 0.5], RED, [0.25, 0.25], "solid"), None);
+    });
+
+    assert_eq!(frame.get_pixel(40, 20), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(40, 50), &BACKGROUND);
+    // The canvas clips what its camera moves out of it, in screen space
+    assert_eq!(frame.get_pixel(24, 32), &BACKGROUND);
+    // Elements outside the canvas keep the fixed camera
+    assert_eq!(frame.get_pixel(8, 8), &Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn canvas_text_follows_its_camera() {
+    let label = || Label::new("MMMM", [0.75, 0.5], [0.5, 1.0], [1.0; 4]);
+    let has_text = |frame: &RgbaImage| frame.enumerate_pixels().any(|(x, _, pixel)| x >= 32 && pixel != &BACKGROUND);
+
+    assert!(has_text(&render_canvas(Camera2D::new(64, 64), |ui| ui.add_element(label(), None))));
+
+    let mut panned = Camera2D::new(64, 64);
+    panned.set_position([1000.0, 0.0]);
+    assert!(!has_text(&render_canvas(panned, |ui| ui.add_element(label(), None))));
+}
+
+#[test]
+fn canvas_elements_are_hit_in_world_space() {
+    let mut interface = Interface::new(test_atlas());
+    let camera = interface.add_camera(zoomed_camera());
+    interface.show(|ui| {
+        ui.canvas(camera, [0.5, 0.5], [1.0, 1.0], |ui| {
+            ui.add_element(Button::new([0.5, 0.5], RED, [0.25, 0.25], "solid"), Some(4));
+        });
+    });
+
+    // Without the camera the button would only cover 24..40
+    assert_eq!(interface.element_at([20.0, 46.0], [64, 64]).map(|element| element.get_id()), Some(4));
+    assert!(interface.element_at([10.0, 10.0], [64, 64]).is_none());
+
+    interface.camera_mut(camera).unwrap().pan([20.0, 0.0]);
+    assert!(interface.element_at([20.0, 46.0], [64, 64]).is_none());
+}
@@ -0,0 +1,60 @@
+use image::{Rgba, RgbaImage};
+use rendering::{snapshot::SnapshotTest, user_interface::{elements::{Button, Label, Panel}, interface::Interface}};
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+fn has_text(frame: &RgbaImage, columns: std::ops::Range<u32>) -> bool {
+    columns.into_iter().any(|x| (0..frame.height()).any(|y| frame.get_pixel(x, y) != &BACKGROUND))
+}
+
+#[test]
+fn quads_are_clipped_to_nested_clip_rects() {
+    let frame = SnapshotTest::new("clipped_quads", [64, 64], test_atlas())
+        .render(|ui| {
+            // Left half, then the top half of that
+            ui.clip([0.25, 0.5], [0.5, 1.0], |ui| {
+                ui.clip([0.5, 0.25], [1.0, 0.5], |ui| {
+                    ui.add_element(Panel::new([0.5, 0.5], RED, [1.0, 1.0], "solid"), None);
+                });
+            });
+        })
+        .unwrap();
+
+    assert_eq!(frame.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(31, 31), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(32, 16), &BACKGROUND);
+    assert_eq!(frame.get_pixel(16, 32), &BACKGROUND);
+    assert_eq!(frame.get_pixel(48, 48), &BACKGROUND);
+}
+
+#[test]
+fn text_is_clipped_with_scissor_rects() {
+    let snapshot = SnapshotTest::new("clipped_text", [96, 48], test_atlas());
+    let label = || Label::new("MMMM", [0.5, 0.5], [1.0, 1.0], [1.0; 4]);
+
+    let unclipped = snapshot.render(|ui| ui.add_element(label(), None)).unwrap();
+    assert!(has_text(&unclipped, 0..48));
+    assert!(has_text(&unclipped, 48..96));
+
+    let clipped = snapshot.render(|ui| ui.clip([0.25, 0.5], [0.5, 1.0], |ui| ui.add_element(label(), None))).unwrap();
+    assert!(has_text(&clipped, 0..48));
+    assert!(!has_text(&clipped, 48..96));
+}
+
+#[test]
+fn clipped_parts_of_elements_are_not_hit() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.clip([0.25, 0.5], [0.5, 1.0], |ui| {
+            ui.add_element(Button::new([0.5, 0.5], RED, [1.0, 1.0], "solid"), Some(3));
+        });
+    });
+
+    assert_eq!(interface.element_at([25.0, 50.0], [100, 100]).map(|element| element.get_id()), Some(3));
+    assert!(interface.element_at([75.0, 50.0], [100, 100]).is_none());
+}
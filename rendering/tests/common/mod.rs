@@ -0,0 +1,19 @@
+use image::{Rgba, RgbaImage};
+use rendering::definitions::{UiAtlas, UiAtlasTexture};
+
+/// A small atlas with an opaque "solid" texture and a hollow "outline" texture.
+pub fn test_atlas() -> UiAtlas {
+    let mut image = RgbaImage::new(20, 8);
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let is_solid = x < 8;
+        let is_outline = (12..20).contains(&x) && (x == 12 || x == 19 || y == 0 || y == 7);
+        if is_solid || is_outline {
+            *pixel = Rgba([255, 255, 255, 255]);
+        }
+    }
+
+    let mut atlas = UiAtlas::from_image(image);
+    atlas.add_entry(UiAtlasTexture::new("solid".to_string(), 0, 0, 8, 8));
+    atlas.add_entry(UiAtlasTexture::new("outline".to_string(), 12, 0, 8, 8));
+    atlas
+}
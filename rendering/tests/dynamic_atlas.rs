@@ -0,0 +1,56 @@
+use std::sync::{Arc, Mutex};
+
+use image::{Rgba, RgbaImage};
+use rendering::{user_interface::interface::Interface, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+#[test]
+fn runtime_textures_grow_the_atlas() {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let mut render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(64, 64), interface.clone())).unwrap();
+
+    let avatar = render_state.add_texture("avatar", &RgbaImage::from_pixel(32, 32, Rgba([255, 0, 0, 255]))).unwrap();
+    assert!(render_state.add_texture("avatar", &RgbaImage::new(4, 4)).is_err());
+
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            ui.add_panel([0.25, 0.5], "#ffffffff", [0.5, 1.0], "avatar", None);
+            ui.add_panel([0.75, 0.5], "#ffffffff", [0.5, 1.0], "solid", None);
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [64, 64]);
+    }
+
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(16, 32), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(48, 32), &Rgba([255, 255, 255, 255]));
+
+    assert!(render_state.remove_texture(avatar));
+    assert!(!render_state.remove_texture(avatar));
+    assert!(interface.lock().unwrap().atlas().get_entry_by_name("avatar".to_string()).is_none());
+}
+
+#[test]
+fn textures_can_be_added_before_the_text_brush() {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let mut render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(64, 64), interface.clone())).unwrap();
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            ui.add_panel([0.5, 0.5], "#ffffffff", [1.0, 1.0], "solid", None);
+        });
+        interface_guard.update_instances(&render_state.device, &render_state.queue, [64, 64]);
+    }
+
+    // Large enough to grow the atlas, which rebuilds the instance buffers
+    let size_before = interface.lock().unwrap().atlas().size();
+    render_state.add_texture("banner", &RgbaImage::new(64, 64)).unwrap();
+    assert_ne!(interface.lock().unwrap().atlas().size(), size_before);
+}
@@ -0,0 +1,71 @@
+use rendering::user_interface::{elements::{Button, ElementType, Panel}, interface::Interface};
+
+use common::test_atlas;
+
+mod common;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+#[test]
+fn children_are_placed_in_their_parent() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.panel(Panel::new([0.75, 0.75], WHITE, [0.5, 0.5], "solid"), None, |ui| {
+            ui.panel(Panel::new([0.5, 0.5], WHITE, [0.5, 0.5], "solid"), None, |ui| {
+                ui.add_element(Panel::new([0.0, 0.0], WHITE, [1.0, 1.0], "solid"), None);
+            });
+        });
+    });
+
+    let rects: Vec<([f32; 2], [f32; 2])> = interface.elements.iter_mut()
+        .map(|element| (element.get_position([80, 80]), element.get_scale([80, 80])))
+        .collect();
+    assert_eq!(rects, [
+        ([60.0, 60.0], [40.0, 40.0]),
+        ([60.0, 60.0], [20.0, 20.0]),
+        ([50.0, 50.0], [20.0, 20.0]),
+    ]);
+
+    let keys: Vec<&str> = interface.elements.iter().map(|element| element.get_key()).collect();
+    assert_eq!(keys, ["0", "0/0", "0/0/0"]);
+}
+
+#[test]
+fn children_only_take_input_inside_their_parent() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.panel(Panel::new([0.25, 0.5], WHITE, [0.5, 1.0], "solid"), Some(1), |ui| {
+            // Half of the button hangs over the parent's right edge
+            ui.add_element(Button::new([1.0, 0.5], WHITE, [0.5, 0.5], "solid"), Some(2));
+        });
+    });
+
+    let hit = |interface: &mut Interface, cursor_position| interface.element_at(cursor_position, [80, 80]).map(|element| element.get_element_type());
+    assert!(hit(&mut interface, [35.0, 40.0]) == Some(ElementType::Button(2)));
+    assert!(hit(&mut interface, [10.0, 40.0]) == Some(ElementType::Panel(1)));
+    assert!(hit(&mut interface, [45.0, 40.0]).is_none());
+}
+
+#[test]
+fn removing_a_parent_removes_its_children() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.panel(Panel::new([0.5, 0.5], WHITE, [1.0, 1.0], "solid"), Some(1), |ui| {
+            ui.panel(Panel::new([0.5, 0.5], WHITE, [0.5, 0.5], "solid"), Some(2), |ui| {
+                ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.5, 0.5], "solid"), Some(3));
+            });
+            ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.5, 0.5], "solid"), Some(4));
+        });
+        ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.5, 0.5], "solid"), Some(5));
+    });
+    assert_eq!(interface.children_of(1), [2, 4]);
+    assert_eq!(interface.parent_of(3), Some(2));
+
+    assert_eq!(interface.remove_element(2), 2);
+    assert_eq!(interface.children_of(1), [4]);
+
+    assert_eq!(interface.remove_element(1), 2);
+    let ids: Vec<u32> = interface.elements.iter().map(|element| element.get_id()).collect();
+    assert_eq!(ids, [5]);
+    assert_eq!(interface.parent_of(4), None);
+}
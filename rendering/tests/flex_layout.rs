@@ -0,0 +1,87 @@
+use rendering::user_interface::{elements::{Icon, Panel}, interface::Interface, layout::{Align, Flex, FlexBasis, FlexItem, Justify}};
+
+use common::test_atlas;
+
+mod common;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Center and size of every element, rounded to whole pixels.
+fn rects(interface: &mut Interface, window_size: [u32; 2]) -> Vec<([f32; 2], [f32; 2])> {
+    interface.update_layout(window_size);
+    interface.elements.iter_mut()
+        .map(|element| (element.get_position(window_size).map(f32::round), element.get_scale(window_size).map(f32::round)))
+        .collect()
+}
+
+fn fill() -> Panel {
+    Panel::new([0.5, 0.5], WHITE, [1.0, 1.0], "solid")
+}
+
+#[test]
+fn rows_grow_into_free_space_and_follow_the_window() {
+    let mut interface = Interface::new(test_atlas());
+    let fixed = FlexItem::new().with_basis(FlexBasis::Pixels(20.0));
+    interface.show(|ui| ui.row(Flex::new().with_gap(10.0).with_padding(5.0), |ui| {
+        ui.flex_item(fixed, |ui| ui.add_element(fill(), None));
+        ui.flex_item(FlexItem::new().with_basis(FlexBasis::Pixels(0.0)).with_grow(1.0), |ui| ui.add_element(fill(), None));
+        ui.flex_item(fixed, |ui| ui.add_element(fill(), None));
+    }));
+
+    assert_eq!(rects(&mut interface, [100, 20]), [
+        ([15.0, 10.0], [20.0, 10.0]),
+        ([50.0, 10.0], [30.0, 10.0]),
+        ([85.0, 10.0], [20.0, 10.0]),
+    ]);
+    assert_eq!(rects(&mut interface, [200, 20]), [
+        ([15.0, 10.0], [20.0, 10.0]),
+        ([100.0, 10.0], [130.0, 10.0]),
+        ([185.0, 10.0], [20.0, 10.0]),
+    ]);
+}
+
+#[test]
+fn columns_justify_and_align_their_items() {
+    let mut interface = Interface::new(test_atlas());
+    let flex = Flex::new().with_justify(Justify::Center).with_align(Align::Center);
+    interface.show(|ui| ui.column(flex, |ui| {
+        ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.5, 0.2], "solid"), None);
+        ui.flex_item(FlexItem::new().with_align(Align::End), |ui| ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.5, 0.2], "solid"), None));
+    }));
+
+    assert_eq!(rects(&mut interface, [100, 100]), [
+        ([50.0, 40.0], [50.0, 20.0]),
+        ([75.0, 60.0], [50.0, 20.0]),
+    ]);
+}
+
+#[test]
+fn items_shrink_by_their_basis_when_they_overflow() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| ui.row(Flex::new(), |ui| {
+        ui.flex_item(FlexItem::new().with_basis(FlexBasis::Pixels(80.0)), |ui| ui.add_element(fill(), None));
+        ui.flex_item(FlexItem::new().with_basis(FlexBasis::Pixels(40.0)), |ui| ui.add_element(fill(), None));
+        ui.flex_item(FlexItem::new().with_basis(FlexBasis::Pixels(30.0)).with_shrink(0.0), |ui| ui.add_element(fill(), None));
+    }));
+
+    let widths: Vec<f32> = rects(&mut interface, [120, 10]).iter().map(|(_, scale)| scale[0]).collect();
+    assert_eq!(widths, [60.0, 30.0, 30.0]);
+}
+
+#[test]
+fn children_follow_items_that_were_laid_out() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.panel(Panel::new([0.5, 0.25], WHITE, [1.0, 0.5], "solid"), None, |ui| {
+            ui.row(Flex::new().with_justify(Justify::End), |ui| {
+                ui.flex_item(FlexItem::new().with_basis(FlexBasis::Pixels(20.0)), |ui| {
+                    ui.parent(fill(), None, |ui| ui.add_element(Icon::new([0.5, 0.5], WHITE, [8.0, 8.0], "solid"), None));
+                });
+            });
+        });
+    });
+
+    let rects = rects(&mut interface, [100, 40]);
+    assert_eq!(rects[1], ([90.0, 10.0], [20.0, 20.0]));
+    assert_eq!(rects[2], ([90.0, 10.0], [8.0, 8.0]));
+}
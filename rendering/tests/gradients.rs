@@ -0,0 +1,59 @@
+use image::RgbaImage;
+use rendering::{snapshot::SnapshotTest, user_interface::elements::{Gradient, QuadStyle}};
+
+use common::test_atlas;
+
+mod common;
+
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+// Frames are sRGB, so channels only a few percent into a stop already read around 30
+fn render_gradient(gradient: Gradient) -> RgbaImage {
+    SnapshotTest::new("gradient", [64, 32], test_atlas())
+        .render(|ui| {
+            ui.add_panel_styled([0.5, 0.5], "#ffffffff", [1.0, 1.0], "solid", None, QuadStyle::new().with_gradient(gradient));
+        })
+        .unwrap()
+}
+
+#[test]
+fn linear_gradients_follow_their_angle() {
+    let horizontal = render_gradient(Gradient::linear(0.0).with_stop(0.0, RED).with_stop(1.0, BLUE));
+    let left = horizontal.get_pixel(0, 16);
+    let right = horizontal.get_pixel(63, 16);
+    assert!(left[0] > 250 && left[2] < 60, "{left:?}");
+    assert!(right[2] > 250 && right[0] < 60, "{right:?}");
+    assert_eq!(horizontal.get_pixel(16, 0), horizontal.get_pixel(16, 31));
+
+    let vertical = render_gradient(Gradient::linear(90.0).with_stop(0.0, RED).with_stop(1.0, BLUE));
+    assert!(vertical.get_pixel(32, 0)[0] > 240);
+    assert!(vertical.get_pixel(32, 31)[2] > 240);
+    assert_eq!(vertical.get_pixel(0, 8), vertical.get_pixel(63, 8));
+}
+
+#[test]
+fn stops_are_blended_in_order() {
+    let frame = render_gradient(Gradient::linear(0.0).with_stop(0.0, RED).with_stop(0.5, GREEN).with_stop(1.0, BLUE));
+    let middle = frame.get_pixel(32, 16);
+    assert!(middle[1] > 240 && middle[0] < 60 && middle[2] < 60, "{middle:?}");
+    let quarter = frame.get_pixel(16, 16);
+    assert!(quarter[0] > 100 && quarter[1] > 100 && quarter[2] < 60, "{quarter:?}");
+}
+
+#[test]
+fn radial_gradients_spread_from_their_center() {
+    let frame = render_gradient(Gradient::radial([0.5, 0.5]).with_stop(0.0, RED).with_stop(1.0, BLUE));
+    let center = frame.get_pixel(32, 16);
+    let corner = frame.get_pixel(0, 0);
+    assert!(center[0] > 240 && center[2] < 60, "{center:?}");
+    assert!(corner[2] > 240 && corner[0] < 60, "{corner:?}");
+    assert_eq!(frame.get_pixel(0, 0), frame.get_pixel(63, 31));
+}
+
+#[test]
+#[should_panic]
+fn gradients_have_a_limited_number_of_stops() {
+    let _ = (0..5).fold(Gradient::linear(0.0), |gradient, stop| gradient.with_stop(stop as f32 / 4.0, RED));
+}
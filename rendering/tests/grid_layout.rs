@@ -0,0 +1,87 @@
+use rendering::user_interface::{elements::Panel, interface::Interface, layout::{Grid, GridItem, Track}};
+
+use common::test_atlas;
+
+mod common;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// `[left, top, right, bottom]` of every element, rounded to whole pixels.
+fn rects(interface: &mut Interface, window_size: [u32; 2]) -> Vec<[f32; 4]> {
+    interface.update_layout(window_size);
+    interface.elements.iter_mut().map(|element| {
+        let position = element.get_position(window_size);
+        let scale = element.get_scale(window_size);
+        [position[0] - scale[0] / 2.0, position[1] - scale[1] / 2.0, position[0] + scale[0] / 2.0, position[1] + scale[1] / 2.0].map(f32::round)
+    }).collect()
+}
+
+fn fill() -> Panel {
+    Panel::new([0.5, 0.5], WHITE, [1.0, 1.0], "solid")
+}
+
+#[test]
+fn tracks_are_fixed_fractional_or_fit_their_items() {
+    let mut interface = Interface::new(test_atlas());
+    let grid = Grid::new()
+        .with_columns(&[Track::Pixels(20.0), Track::Auto, Track::Fraction(1.0), Track::Fraction(3.0)])
+        .with_rows(&[Track::Fraction(1.0)])
+        .with_gap(4.0);
+    interface.show(|ui| ui.grid(grid, |ui| {
+        ui.add_element(fill(), None);
+        // Auto columns fit the scale the element was built with
+        ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.1, 1.0], "solid"), None);
+        ui.add_element(fill(), None);
+        ui.add_element(fill(), None);
+    }));
+
+    assert_eq!(rects(&mut interface, [100, 40]), [
+        [0.0, 0.0, 20.0, 40.0],
+        [24.0, 0.0, 34.0, 40.0],
+        [38.0, 0.0, 53.0, 40.0],
+        [57.0, 0.0, 100.0, 40.0],
+    ]);
+    // Fractions share whatever the window leaves them
+    assert_eq!(rects(&mut interface, [200, 40])[3], [89.0, 0.0, 200.0, 40.0]);
+}
+
+#[test]
+fn items_span_cells_and_fill_the_free_ones() {
+    let mut interface = Interface::new(test_atlas());
+    let grid = Grid::new()
+        .with_columns(&[Track::Fraction(1.0); 3])
+        .with_rows(&[Track::Pixels(10.0); 3]);
+    interface.show(|ui| ui.grid(grid, |ui| {
+        ui.add_element(fill(), None);
+        ui.grid_item(GridItem::at(1, 0).with_span(2, 2), |ui| ui.add_element(fill(), None));
+        ui.add_element(fill(), None);
+        ui.grid_item(GridItem::new().with_span(2, 1), |ui| ui.add_element(fill(), None));
+    }));
+
+    assert_eq!(rects(&mut interface, [90, 30]), [
+        [0.0, 0.0, 30.0, 10.0],
+        [30.0, 0.0, 90.0, 20.0],
+        [0.0, 10.0, 30.0, 20.0],
+        [0.0, 20.0, 60.0, 30.0],
+    ]);
+}
+
+#[test]
+fn items_are_placed_in_named_areas() {
+    let mut interface = Interface::new(test_atlas());
+    let grid = Grid::new()
+        .with_columns(&[Track::Pixels(30.0), Track::Fraction(1.0)])
+        .with_rows(&[Track::Pixels(10.0), Track::Fraction(1.0)])
+        .with_areas(&["header header", "sidebar main"]);
+    interface.show(|ui| ui.grid(grid, |ui| {
+        ui.grid_item(GridItem::area("main"), |ui| ui.add_element(fill(), None));
+        ui.grid_item(GridItem::area("header"), |ui| ui.add_element(fill(), None));
+        ui.grid_item(GridItem::area("sidebar"), |ui| ui.add_element(fill(), None));
+    }));
+
+    assert_eq!(rects(&mut interface, [100, 50]), [
+        [30.0, 10.0, 100.0, 50.0],
+        [0.0, 0.0, 100.0, 10.0],
+        [0.0, 10.0, 30.0, 50.0],
+    ]);
+}
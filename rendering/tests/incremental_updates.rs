@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+
+use image::Rgba;
+use rendering::{user_interface::{elements::{Button, Panel, QuadStyle, Shadow, WidgetState}, interface::Interface}, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+#[test]
+fn only_changed_instances_are_written() {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(80, 8), interface.clone())).unwrap();
+    let update = || interface.lock().unwrap().update_instances(&render_state.device, &render_state.queue, [80, 8]);
+
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            for column in 0..10 {
+                ui.add_element(Button::new([(column as f32 + 0.5) / 10.0, 0.5], RED, [0.1, 1.0], "solid"), Some(column));
+            }
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [80, 8]);
+    }
+    assert_eq!(update(), 0);
+
+    interface.lock().unwrap().elements[3].set_highlight(0.0);
+    assert_eq!(update(), 1);
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(28, 4), &BACKGROUND);
+    assert_eq!(frame.get_pixel(36, 4), &Rgba([255, 0, 0, 255]));
+
+    // New elements move every element's depth, so everything is written again
+    interface.lock().unwrap().show(|ui| ui.add_element(Panel::new([0.35, 0.5], [0.0, 0.0, 1.0, 1.0], [0.1, 1.0], "solid").with_z_index(1), None));
+    assert_eq!(update(), 11);
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(28, 4), &Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn only_dirty_elements_are_rebuilt() {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(80, 8), interface.clone())).unwrap();
+    let update = || interface.lock().unwrap().update_instances(&render_state.device, &render_state.queue, [80, 8]);
+
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            for column in 0..10 {
+                let shadowed = QuadStyle::new().with_shadow(Shadow::from_elevation(1.0));
+                ui.add_element(Button::new([(column as f32 + 0.5) / 10.0, 0.5], RED, [0.1, 1.0], "solid").with_style(shadowed), None);
+            }
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [80, 8]);
+        assert!(interface_guard.elements.iter().all(|element| !element.is_dirty()));
+    }
+
+    // Setting the highlight an element already has leaves it clean
+    interface.lock().unwrap().elements[2].set_highlight(0.5);
+    assert_eq!(update(), 1);
+    interface.lock().unwrap().elements[2].set_highlight(0.5);
+    assert!(!interface.lock().unwrap().elements[2].is_dirty());
+    assert_eq!(update(), 0);
+
+    // Restoring state marks the element dirty, its shadow keeps its slot
+    let before = render_state.read_frame().unwrap();
+    interface.lock().unwrap().elements[5].set_state(WidgetState::Highlight(0.0));
+    assert_eq!(update(), 1);
+    let after = render_state.read_frame().unwrap();
+    assert_ne!(after.get_pixel(44, 4), before.get_pixel(44, 4));
+    assert_eq!(after.get_pixel(12, 4), before.get_pixel(12, 4));
+}
@@ -0,0 +1,44 @@
+use rendering::snapshot::SnapshotTest;
+
+use common::test_atlas;
+
+mod common;
+
+#[test]
+fn panels_and_buttons() {
+    SnapshotTest::new("panels_and_buttons", [320, 240], test_atlas()).assert_matches(|ui| {
+        ui.add_panel([0.5, 0.05], "#0d1117ff", [1.0, 0.1], "solid", Some(0));
+        ui.add_panel([0.25, 0.55], "#30363dff", [0.4, 0.7], "solid", None);
+        ui.add_button([0.75, 0.55], "#5c0303ff", [0.3, 0.2], Box::new(|| {}), "solid");
+    });
+}
+
+#[test]
+fn icons_keep_pixel_size() {
+    SnapshotTest::new("icons_keep_pixel_size", [320, 240], test_atlas()).assert_matches(|ui| {
+        ui.add_icon([0.25, 0.5], "#ffffffff", [8.0, 8.0], "outline");
+        ui.add_icon([0.5, 0.5], "#ffffffff", [16.0, 16.0], "outline");
+        ui.add_icon([0.75, 0.5], "#ffffffff", [32.0, 32.0], "outline");
+    });
+}
+
+#[test]
+fn icons_follow_the_scale_factor() {
+    SnapshotTest::new("icons_follow_the_scale_factor", [320, 240], test_atlas())
+        .with_scale_factor(2.0)
+        .assert_matches(|ui| {
+            ui.add_icon([0.25, 0.5], "#ffffffff", [8.0, 8.0], "outline");
+            ui.add_icon([0.5, 0.5], "#ffffffff", [16.0, 16.0], "outline");
+            ui.add_icon([0.75, 0.5], "#ffffffff", [32.0, 32.0], "outline");
+        });
+}
+
+#[test]
+fn labels() {
+    SnapshotTest::new("labels", [320, 240], test_atlas())
+        .with_max_mismatched_pixels(16)
+        .assert_matches(|ui| {
+            ui.add_panel([0.5, 0.5], "#0d1117ff", [0.8, 0.4], "solid", None);
+            ui.add_label("Snapshot", [0.5, 0.5], [0.8, 0.4], "#ffffffff");
+        });
+}
@@ -0,0 +1,67 @@
+use image::Rgba;
+use rendering::{snapshot::SnapshotTest, user_interface::{elements::{Button, Label, Panel}, interface::Interface, UserInterface}};
+
+use common::test_atlas;
+
+mod common;
+
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+#[test]
+fn higher_z_index_is_drawn_on_top() {
+    let frame = SnapshotTest::new("z_index", [32, 32], test_atlas())
+        .render(|ui| {
+            ui.add_element(Panel::new([0.5, 0.5], RED, [1.0, 1.0], "solid").with_z_index(1), None);
+            ui.add_element(Panel::new([0.5, 0.5], BLUE, [1.0, 1.0], "solid"), None);
+        })
+        .unwrap();
+
+    assert_eq!(frame.get_pixel(16, 16), &Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+fn panels_hide_labels_below_them() {
+    let label = |z_index| move |ui: &mut UserInterface| {
+        ui.add_element(Panel::new([0.5, 0.5], RED, [1.0, 1.0], "solid").with_z_index(z_index), None);
+        ui.add_element(Label::new("MMMM", [0.5, 0.5], [1.0, 1.0], [1.0; 4]), None);
+    };
+    let snapshot = SnapshotTest::new("label_layering", [96, 48], test_atlas());
+
+    let covered = snapshot.render(label(1)).unwrap();
+    assert!(covered.pixels().all(|pixel| pixel == &Rgba([255, 0, 0, 255])));
+
+    let uncovered = snapshot.render(label(-1)).unwrap();
+    assert!(uncovered.pixels().any(|pixel| pixel != &Rgba([255, 0, 0, 255])));
+}
+
+#[test]
+fn translucent_panels_blend_over_labels_below_them() {
+    let label = |z_index| move |ui: &mut UserInterface| {
+        ui.add_element(Panel::new([0.5, 0.5], [1.0, 0.0, 0.0, 0.5], [1.0, 1.0], "solid").with_z_index(z_index), None);
+        ui.add_element(Label::new("MMMM", [0.5, 0.5], [1.0, 1.0], [1.0; 4]), None);
+    };
+    let snapshot = SnapshotTest::new("translucent_label_layering", [96, 48], test_atlas());
+    let brightest_green = |frame: image::RgbaImage| frame.pixels().map(|pixel| pixel[1]).max().unwrap();
+
+    // The panel tints the text under it, so it's never as bright as uncovered text
+    let covered = brightest_green(snapshot.render(label(1)).unwrap());
+    let uncovered = brightest_green(snapshot.render(label(-1)).unwrap());
+    assert_eq!(uncovered, 255);
+    assert!(covered > 64 && covered < 224, "covered text has a green of {covered}");
+}
+
+#[test]
+fn hit_testing_follows_z_index() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.add_element(Button::new([0.5, 0.5], RED, [1.0, 1.0], "solid").with_z_index(2), Some(7));
+        ui.add_element(Button::new([0.5, 0.5], BLUE, [0.5, 0.5], "solid"), Some(8));
+        // Labels don't take input so they never block the buttons
+        ui.add_element(Label::new("label", [0.5, 0.5], [1.0, 1.0], [1.0; 4]).with_z_index(3), None);
+    });
+
+    let topmost = interface.element_at([50.0, 50.0], [100, 100]).map(|element| element.get_id());
+    assert_eq!(topmost, Some(7));
+    assert!(interface.element_at([150.0, 50.0], [100, 100]).is_none());
+}
@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+
+use image::Rgba;
+use rendering::{material::Material, user_interface::{elements::{Label, Panel}, interface::Interface}, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+const TINT: &str = "
+struct Tint {
+    color: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> tint: Tint;
+
+@fragment
+fn tint_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return tint.color;
+}
+";
+
+const BLUE: &str = "
+@fragment
+fn blue_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0, 0.0, 1.0, 1.0);
+}
+";
+
+const GLASS: &str = "
+@fragment
+fn glass_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0, 0.0, 1.0, 0.25);
+}
+";
+
+#[test]
+fn elements_are_drawn_with_their_material() {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let mut render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(96, 32), interface.clone())).unwrap();
+
+    let red: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    render_state.register_material(Material::new("tint", TINT, "tint_main").with_uniforms(bytemuck::cast_slice(&red))).unwrap();
+    render_state.register_material(Material::new("blue", BLUE, "blue_main")).unwrap();
+    assert!(render_state.register_material(Material::new("broken", "@fragment fn broken(", "broken")).is_err());
+
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            ui.add_panel([1.0 / 6.0, 0.5], "#ffffffff", [1.0 / 3.0, 1.0], "solid", None);
+            ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [1.0 / 3.0, 1.0], "solid").with_material("tint"), None);
+            ui.add_element(Panel::new([5.0 / 6.0, 0.5], [1.0; 4], [1.0 / 3.0, 1.0], "solid").with_material("blue"), None);
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [96, 32]);
+    }
+
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(16, 16), &Rgba([255, 255, 255, 255]));
+    assert_eq!(frame.get_pixel(48, 16), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(80, 16), &Rgba([0, 0, 255, 255]));
+
+    let green: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+    render_state.set_material_uniforms("tint", bytemuck::cast_slice(&green)).unwrap();
+    assert!(render_state.set_material_uniforms("blue", bytemuck::cast_slice(&green)).is_err());
+
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(48, 16), &Rgba([0, 255, 0, 255]));
+}
+
+#[test]
+fn translucent_materials_show_the_text_under_them() {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let mut render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(128, 64), interface.clone())).unwrap();
+    render_state.register_material(Material::new("glass", GLASS, "glass_main")).unwrap();
+
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| {
+            ui.add_element(Label::new("Glass", [0.5, 0.5], [1.0, 0.5], [1.0, 0.0, 0.0, 1.0]).with_text_size(24.0), None);
+            ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [1.0, 1.0], "solid").with_material("glass"), None);
+        });
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [128, 64]);
+    }
+    let red_pixels = |render_state: &RenderState| {
+        render_state.read_frame().unwrap().pixels().filter(|pixel| pixel[0] > pixel[2].saturating_add(64)).count()
+    };
+
+    // Opaque materials write depth, hiding the label behind them
+    assert_eq!(red_pixels(&render_state), 0);
+
+    render_state.register_material(Material::new("glass", GLASS, "glass_main").with_translucency()).unwrap();
+    interface.lock().unwrap().update_instances(&render_state.device, &render_state.queue, [128, 64]);
+    assert!(red_pixels(&render_state) > 0);
+
+    assert!(render_state.register_material(Material::new("glass_translucent", BLUE, "blue_main")).is_err());
+}
@@ -0,0 +1,35 @@
+use image::RgbaImage;
+use rendering::{snapshot::SnapshotTest, user_interface::elements::Panel};
+
+use common::test_atlas;
+
+mod common;
+
+/// Renders a white panel whose left edge falls halfway through pixel column 10.
+fn render_edge(sample_count: u32) -> RgbaImage {
+    SnapshotTest::new("msaa_edge", [32, 8], test_atlas())
+        .with_sample_count(sample_count)
+        .render(|ui| {
+            ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [0.34375, 1.0], "solid"), None);
+        })
+        .unwrap()
+}
+
+#[test]
+fn multisampling_blends_partially_covered_pixels() {
+    let aliased = render_edge(1);
+    let edge = aliased.get_pixel(10, 4)[0];
+    assert!(edge == 0x21 || edge == 255, "pixel was partially covered without MSAA: {edge}");
+
+    let antialiased = render_edge(4);
+    let edge = antialiased.get_pixel(10, 4)[0];
+    assert!(edge > 0x21 && edge < 255, "edge pixel wasn't blended: {edge}");
+    assert_eq!(antialiased.get_pixel(16, 4)[0], 255);
+    assert_eq!(antialiased.get_pixel(4, 4)[0], 0x21);
+}
+
+#[test]
+fn unsupported_sample_counts_are_rejected() {
+    let snapshot = SnapshotTest::new("msaa_invalid", [32, 8], test_atlas()).with_sample_count(3);
+    assert!(snapshot.render(|_| {}).is_err());
+}
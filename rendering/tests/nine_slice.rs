@@ -0,0 +1,34 @@
+use image::{Rgba, RgbaImage};
+use rendering::{definitions::UiAtlasTexture, snapshot::SnapshotTest, user_interface::elements::Panel};
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+
+fn render_outline(texture_name: &str) -> RgbaImage {
+    let mut atlas = test_atlas();
+    // The same one texel outline as "outline", with its edges kept as they are
+    atlas.add_entry(UiAtlasTexture::new("outline_sliced".to_string(), 12, 0, 8, 8).with_nine_slice([1; 4]));
+
+    SnapshotTest::new("nine_slice", [64, 64], atlas)
+        .render(|ui| {
+            ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [1.0, 1.0], texture_name), None);
+        })
+        .unwrap()
+}
+
+#[test]
+fn nine_slice_edges_keep_their_size() {
+    let stretched = render_outline("outline");
+    assert_ne!(stretched.get_pixel(4, 32), &BACKGROUND);
+
+    let sliced = render_outline("outline_sliced");
+    assert_eq!(sliced.get_pixel(0, 32), &Rgba([255, 255, 255, 255]));
+    assert_eq!(sliced.get_pixel(63, 32), &Rgba([255, 255, 255, 255]));
+    assert_eq!(sliced.get_pixel(32, 0), &Rgba([255, 255, 255, 255]));
+    assert_eq!(sliced.get_pixel(4, 32), &BACKGROUND);
+    assert_eq!(sliced.get_pixel(32, 32), &BACKGROUND);
+    assert_eq!(sliced.get_pixel(32, 59), &BACKGROUND);
+}
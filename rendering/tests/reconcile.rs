@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+
+use rendering::{camera::Camera2D, user_interface::{elements::{Button, Panel, TextBox, WidgetState}, interface::Interface}, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// A form with a keyed notes box, optionally after a search box.
+fn build_form(with_search: bool) -> Interface {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        if with_search {
+            ui.add_element(TextBox::new("search", [0.5, 0.1], [1.0, 0.2], WHITE), None);
+        }
+        ui.keyed("notes", |ui| ui.add_element(TextBox::new("notes", [0.5, 0.5], [1.0, 0.5], WHITE), None));
+        ui.add_element(TextBox::new("footer", [0.5, 0.9], [1.0, 0.2], WHITE), None);
+    });
+    interface
+}
+
+#[test]
+fn state_follows_keys_across_rebuilds() {
+    let mut interface = build_form(false);
+    let keys: Vec<&str> = interface.elements.iter().map(|element| element.get_key()).collect();
+    assert_eq!(keys, ["notes/0", "0"]);
+
+    interface.element_by_key("notes/0").unwrap().set_text("hello", [100, 100]);
+    interface.element_by_key("0").unwrap().set_text("bye", [100, 100]);
+    let notes_id = interface.element_by_key("notes/0").unwrap().get_id();
+
+    let mut rebuilt = build_form(true);
+    let ids = rebuilt.reconcile(interface);
+
+    // The keyed box keeps its text wherever it ends up
+    let notes = rebuilt.element_by_key("notes/0").unwrap();
+    assert_eq!(notes.get_state(), Some(WidgetState::Text("hello".to_string())));
+    assert_eq!(ids.get(&notes_id), Some(&notes.get_id()));
+    // Unkeyed ones are matched by order, so the new search box took the footer's place
+    assert_eq!(rebuilt.elements[0].get_state(), Some(WidgetState::Text("bye".to_string())));
+    assert_eq!(rebuilt.elements[2].get_state(), None);
+}
+
+#[test]
+fn highlights_carry_over_without_replacing_the_built_color() {
+    let build = |color| {
+        let mut interface = Interface::new(test_atlas());
+        interface.show(|ui| ui.add_element(Button::new([0.5, 0.5], color, [1.0, 1.0], "solid"), None));
+        interface
+    };
+
+    let mut interface = build(WHITE);
+    interface.elements[0].set_highlight(0.8);
+
+    // Rebuilt disabled, with a translucent color
+    let mut rebuilt = build([0.5, 0.5, 0.5, 0.4]);
+    rebuilt.reconcile(interface);
+    assert_eq!(rebuilt.elements[0].get_state(), Some(WidgetState::Highlight(0.8)));
+    assert_eq!(rebuilt.elements[0].get_color(), [0.5, 0.5, 0.5, 0.4]);
+
+    // Without a highlight to carry over the new color is all there is
+    let mut rebuilt_again = build([0.5, 0.5, 0.5, 0.4]);
+    rebuilt_again.reconcile(build(WHITE));
+    assert_eq!(rebuilt_again.elements[0].get_highlight(), None);
+    assert_eq!(rebuilt_again.elements[0].get_color()[3], 0.4);
+}
+
+#[test]
+fn rebuilds_reuse_buffers_and_cameras() {
+    let build = || {
+        let mut interface = Interface::new(test_atlas());
+        let camera = interface.add_camera(Camera2D::new(80, 8));
+        interface.show(|ui| ui.canvas(camera, [0.5, 0.5], [1.0, 1.0], |ui| {
+            for column in 0..4 {
+                ui.add_element(Panel::new([(column as f32 + 0.5) / 4.0, 0.5], WHITE, [0.25, 1.0], "solid"), None);
+            }
+        }));
+        (interface, camera)
+    };
+
+    let (interface, camera) = build();
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(interface));
+    let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(80, 8), interface.clone())).unwrap();
+    let mut interface_guard = interface.lock().unwrap();
+    interface_guard.camera_mut(camera).unwrap().set_zoom(2.0);
+    interface_guard.set_zoom(1.5);
+    interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+    interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [80, 8]);
+
+    let (rebuilt, camera) = build();
+    let previous = std::mem::replace(&mut *interface_guard, rebuilt);
+    interface_guard.reconcile(previous);
+
+    assert!(interface_guard.has_text_brush());
+    assert_eq!(interface_guard.camera(camera).unwrap().zoom(), 2.0);
+    assert_eq!(interface_guard.zoom(), 1.5);
+    // The rebuilt interface matches what the reused buffers hold
+    assert_eq!(interface_guard.update_instances(&render_state.device, &render_state.queue, [80, 8]), 0);
+}
+
+#[test]
+fn cameras_follow_their_canvas_key() {
+    let build = |with_minimap: bool| {
+        let mut interface = Interface::new(test_atlas());
+        let minimap = with_minimap.then(|| interface.add_camera(Camera2D::new(64, 64)));
+        let map = interface.add_camera(Camera2D::new(64, 64));
+        interface.show(|ui| {
+            if let Some(minimap) = minimap {
+                ui.canvas(minimap, [0.25, 0.5], [0.5, 1.0], |_| {});
+            }
+            ui.keyed("map", |ui| ui.canvas(map, [0.75, 0.5], [0.5, 1.0], |_| {}));
+        });
+        (interface, minimap, map)
+    };
+
+    let (mut interface, _, map) = build(false);
+    interface.camera_mut(map).unwrap().set_position([12.0, 34.0]);
+    interface.camera_mut(map).unwrap().set_zoom(3.0);
+
+    // The minimap's camera now comes first, the map's view still follows its key
+    let (mut rebuilt, minimap, map) = build(true);
+    rebuilt.reconcile(interface);
+    assert_eq!(rebuilt.camera(map).unwrap().position(), [12.0, 34.0]);
+    assert_eq!(rebuilt.camera(map).unwrap().zoom(), 3.0);
+    assert_eq!(rebuilt.camera(minimap.unwrap()).unwrap().zoom(), 1.0);
+}
@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+use image::Rgba;
+use rendering::{render_config::RenderConfig, user_interface::{elements::Panel, interface::Interface}, RenderState};
+use winit::dpi::PhysicalSize;
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+
+fn headless() -> RenderState {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let interface = Arc::new(Mutex::new(Interface::new(test_atlas())));
+    let render_state = pollster::block_on(RenderState::new_headless(PhysicalSize::new(32, 32), interface.clone())).unwrap();
+    {
+        let mut interface_guard = interface.lock().unwrap();
+        interface_guard.show(|ui| ui.add_element(Panel::new([0.25, 0.5], [1.0, 0.0, 0.0, 1.0], [0.5, 1.0], "solid"), None));
+        interface_guard.initalize_text_brush(&render_state.device, &render_state.config, &render_state.queue);
+        interface_guard.initialize_interface_buffers(&render_state.device, &render_state.queue, [32, 32]);
+    }
+    render_state
+}
+
+#[test]
+fn unsupported_preferences_fall_back() {
+    let mut render_state = headless();
+    let render_config = RenderConfig::new()
+        .with_present_mode(wgpu::PresentMode::Mailbox)
+        .with_alpha_mode(wgpu::CompositeAlphaMode::PreMultiplied)
+        .with_format(wgpu::TextureFormat::Rgba16Float)
+        .with_frame_latency(1);
+    render_state.set_render_config(render_config.clone()).unwrap();
+
+    assert_eq!(render_state.config.present_mode, wgpu::PresentMode::Fifo);
+    assert_eq!(render_state.config.alpha_mode, wgpu::CompositeAlphaMode::Opaque);
+    assert_eq!(render_state.config.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+    assert_eq!(render_state.config.desired_maximum_frame_latency, 1);
+    assert_eq!(render_state.render_config(), &render_config);
+}
+
+#[test]
+fn format_can_change_at_runtime() {
+    let mut render_state = headless();
+
+    render_state.set_render_config(RenderConfig::new().with_format(wgpu::TextureFormat::Bgra8UnormSrgb)).unwrap();
+    assert_eq!(render_state.config.format, wgpu::TextureFormat::Bgra8UnormSrgb);
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(8, 16), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(24, 16), &BACKGROUND);
+
+    // Without the sRGB encode the linear clear color is written as is
+    render_state.set_render_config(RenderConfig::new().with_format(wgpu::TextureFormat::Rgba8Unorm)).unwrap();
+    let frame = render_state.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(8, 16), &Rgba([255, 0, 0, 255]));
+    assert!(frame.get_pixel(24, 16)[2] < BACKGROUND[2]);
+}
@@ -0,0 +1,40 @@
+use image::Rgba;
+use rendering::{snapshot::SnapshotTest, user_interface::elements::QuadStyle};
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+
+#[test]
+fn corners_are_rounded_and_anti_aliased() {
+    let frame = SnapshotTest::new("rounded_corners", [32, 32], test_atlas())
+        .render(|ui| {
+            ui.add_panel_styled([0.5, 0.5], "#ffffffff", [1.0, 1.0], "solid", None, QuadStyle::new().with_corner_radius(12.0));
+        })
+        .unwrap();
+
+    assert_eq!(frame.get_pixel(0, 0), &BACKGROUND);
+    assert_eq!(frame.get_pixel(31, 31), &BACKGROUND);
+    assert_eq!(frame.get_pixel(16, 0), &Rgba([255, 255, 255, 255]));
+    assert_eq!(frame.get_pixel(16, 16), &Rgba([255, 255, 255, 255]));
+    // Pixels the curve passes through are blended with the background
+    assert!(frame.pixels().any(|pixel| pixel[0] > BACKGROUND[0] && pixel[0] < 255));
+}
+
+#[test]
+fn borders_are_drawn_inside_the_edge() {
+    let red = [1.0, 0.0, 0.0, 1.0];
+    let frame = SnapshotTest::new("border", [32, 32], test_atlas())
+        .render(|ui| {
+            ui.add_button_styled([0.5, 0.5], "#ffffffff", [1.0, 1.0], Box::new(|| {}), "solid", QuadStyle::new().with_border(4.0, red));
+        })
+        .unwrap();
+
+    assert_eq!(frame.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(16, 2), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(2, 16), &Rgba([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(16, 16), &Rgba([255, 255, 255, 255]));
+    assert_eq!(frame.get_pixel(16, 6), &Rgba([255, 255, 255, 255]));
+}
@@ -0,0 +1,93 @@
+use image::{Rgba, RgbaImage};
+use rendering::{camera::Camera2D, definitions::UiAtlasTexture, snapshot::SnapshotTest, user_interface::{elements::{Button, Icon, Label, Panel, QuadStyle}, interface::Interface}};
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+/// Number of rows with anything other than the background in them.
+fn text_height(frame: &RgbaImage) -> u32 {
+    frame.rows().filter(|row| row.clone().any(|pixel| pixel != &BACKGROUND)).count() as u32
+}
+
+#[test]
+fn text_is_sized_in_logical_pixels() {
+    let label = |text_size| move |ui: &mut rendering::user_interface::UserInterface| {
+        ui.add_element(Label::new("MM", [0.5, 0.5], [1.0, 1.0], [1.0; 4]).with_text_size(text_size), None);
+    };
+    let snapshot = SnapshotTest::new("scaled_text", [128, 128], test_atlas());
+
+    let small = text_height(&snapshot.render(label(15.0)).unwrap());
+    let large = text_height(&snapshot.render(label(30.0)).unwrap());
+    let scaled = text_height(&snapshot.with_scale_factor(2.0).render(label(15.0)).unwrap());
+
+    assert!(small > 0);
+    assert!(large.abs_diff(small * 2) <= 2, "{large} isn't twice {small}");
+    assert!(scaled.abs_diff(large) <= 1, "{scaled} != {large}");
+}
+
+#[test]
+fn style_sizes_follow_the_scale_factor() {
+    let render = |scale_factor| SnapshotTest::new("scaled_border", [32, 32], test_atlas())
+        .with_scale_factor(scale_factor)
+        .render(|ui| {
+            ui.add_button_styled([0.5, 0.5], "#ffffffff", [1.0, 1.0], Box::new(|| {}), "solid", QuadStyle::new().with_border(2.0, RED));
+        })
+        .unwrap();
+
+    assert_eq!(render(1.0).get_pixel(16, 3), &Rgba([255, 255, 255, 255]));
+    assert_eq!(render(2.0).get_pixel(16, 3), &Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+fn nine_slice_insets_follow_the_scale_factor() {
+    let mut atlas = test_atlas();
+    atlas.add_entry(UiAtlasTexture::new("outline_sliced".to_string(), 12, 0, 8, 8).with_nine_slice([1; 4]));
+    let render = |scale_factor| SnapshotTest::new("scaled_nine_slice", [64, 64], atlas.clone())
+        .with_scale_factor(scale_factor)
+        .render(|ui| ui.add_element(Panel::new([0.5, 0.5], [1.0; 4], [1.0, 1.0], "outline_sliced"), None))
+        .unwrap();
+
+    assert_eq!(render(1.0).get_pixel(1, 32), &BACKGROUND);
+    let scaled = render(3.0);
+    assert_eq!(scaled.get_pixel(1, 32), &Rgba([255, 255, 255, 255]));
+    assert_eq!(scaled.get_pixel(4, 32), &BACKGROUND);
+}
+
+#[test]
+fn icon_sizes_follow_the_scale_factor_and_zoom() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| ui.add_element(Icon::new([0.5, 0.5], RED, [8.0, 4.0], "solid"), None));
+    assert_eq!(interface.elements[0].get_scale([64, 64]), [8.0, 4.0]);
+
+    interface.set_scale_factor(2.0);
+    interface.set_zoom(1.5);
+    assert_eq!(interface.elements[0].get_scale([64, 64]), [24.0, 12.0]);
+}
+
+#[test]
+fn canvas_cameras_zoom_with_the_interface() {
+    let mut interface = Interface::new(test_atlas());
+    let camera = interface.add_camera(Camera2D::new(64, 64));
+    interface.show(|ui| {
+        ui.canvas(camera, [0.5, 0.5], [1.0, 1.0], |ui| {
+            ui.add_element(Button::new([0.5, 0.5], RED, [0.25, 0.25], "solid"), Some(2));
+        });
+    });
+    interface.set_scale_factor(2.0);
+
+    // A world unit is a logical pixel, two physical ones here
+    let world_to_screen = interface.camera(camera).unwrap().world_to_screen([10.0, 5.0]);
+    assert_eq!(world_to_screen, [20.0, 10.0]);
+
+    // Relative positions still cover the same part of the window
+    assert_eq!(interface.element_at([25.0, 38.0], [64, 64]).map(|element| element.get_id()), Some(2));
+    assert!(interface.element_at([20.0, 32.0], [64, 64]).is_none());
+
+    interface.set_zoom(2.0);
+    assert_eq!(interface.ui_scale(), 4.0);
+    assert_eq!(interface.camera(camera).unwrap().world_to_screen([10.0, 5.0]), [40.0, 20.0]);
+}
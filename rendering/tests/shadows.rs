@@ -0,0 +1,60 @@
+use image::Rgba;
+use rendering::{snapshot::SnapshotTest, user_interface::elements::{Label, Panel, QuadStyle, Shadow}};
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+const WHITE: [f32; 4] = [1.0; 4];
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+fn render_shadow(shadow: Shadow) -> image::RgbaImage {
+    // A 16x16 panel in the middle of the frame, covering 24..40 on both axes
+    SnapshotTest::new("shadow", [64, 64], test_atlas())
+        .render(|ui| {
+            ui.add_element(Panel::new([0.5, 0.5], WHITE, [0.25, 0.25], "solid").with_style(QuadStyle::new().with_shadow(shadow)), None);
+        })
+        .unwrap()
+}
+
+#[test]
+fn shadows_are_drawn_under_their_element() {
+    let frame = render_shadow(Shadow::new([0.0, 8.0], 0.0, 2.0, BLACK));
+
+    assert_eq!(frame.get_pixel(32, 32), &Rgba([255, 255, 255, 255]));
+    assert_eq!(frame.get_pixel(32, 45), &Rgba([0, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(23, 45), &Rgba([0, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(32, 51), &BACKGROUND);
+    assert_eq!(frame.get_pixel(32, 20), &BACKGROUND);
+}
+
+#[test]
+fn blurred_shadows_fade_out() {
+    let frame = render_shadow(Shadow::new([0.0, 8.0], 6.0, 0.0, BLACK));
+
+    let darkness = |y| BACKGROUND[2] - frame.get_pixel(32, y)[2];
+    assert!(darkness(44) > darkness(48));
+    assert!(darkness(48) > darkness(52));
+    assert!(darkness(44) < BACKGROUND[2]);
+    assert_eq!(frame.get_pixel(32, 63), &BACKGROUND);
+    assert_eq!(frame.get_pixel(32, 32), &Rgba([255, 255, 255, 255]));
+}
+
+#[test]
+fn text_beside_a_shadow_is_blended_rather_than_cut_off() {
+    // The label is added first, so it sits under the panel and inside its shadow's blur
+    let scene = |style: QuadStyle| move |ui: &mut rendering::user_interface::UserInterface| {
+        ui.add_element(Label::new("Shadow", [0.5, 0.8], [1.0, 0.3], [1.0, 0.0, 0.0, 1.0]).with_text_size(16.0), None);
+        ui.add_element(Panel::new([0.5, 0.35], WHITE, [0.5, 0.4], "solid").with_style(style), None);
+    };
+    let shadowed = QuadStyle::new().with_shadow(Shadow::new([0.0, 4.0], 12.0, 0.0, BLACK));
+    let snapshot = SnapshotTest::new("text_beside_shadow", [128, 64], test_atlas()).with_max_mismatched_pixels(16);
+    let red_pixels = |style| snapshot.render(scene(style)).unwrap().pixels().filter(|pixel| pixel[0] > pixel[1].saturating_add(64)).count();
+
+    let plain = red_pixels(QuadStyle::new());
+    let beside_shadow = red_pixels(shadowed);
+    assert!(plain > 0);
+    assert!(beside_shadow.abs_diff(plain) <= plain / 20, "{beside_shadow} text pixels next to the shadow, {plain} without it");
+    snapshot.assert_matches(scene(shadowed));
+}
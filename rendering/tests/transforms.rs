@@ -0,0 +1,49 @@
+use image::Rgba;
+use rendering::{snapshot::SnapshotTest, user_interface::{elements::{Button, Element, Panel, Transform}, interface::Interface}};
+
+use common::test_atlas;
+
+mod common;
+
+const BACKGROUND: Rgba<u8> = Rgba([0x21, 0x26, 0x2d, 255]);
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+#[test]
+fn rotated_elements_turn_around_their_pivot() {
+    let snapshot = SnapshotTest::new("rotation", [64, 64], test_atlas());
+    // A horizontal bar across the middle of the frame
+    let bar = || Panel::new([0.5, 0.5], RED, [0.5, 0.125], "solid");
+
+    let centered = snapshot.render(|ui| ui.add_element(bar().with_transform(Transform::new().with_rotation(90.0)), None)).unwrap();
+    assert_eq!(centered.get_pixel(32, 20), &Rgba([255, 0, 0, 255]));
+    assert_eq!(centered.get_pixel(32, 44), &Rgba([255, 0, 0, 255]));
+    assert_eq!(centered.get_pixel(20, 32), &BACKGROUND);
+
+    // Turning around its left end swings the bar down from there
+    let pivoted = snapshot.render(|ui| {
+        ui.add_element(bar().with_transform(Transform::new().with_rotation(90.0).with_pivot([0.0, 0.5])), None);
+    }).unwrap();
+    assert_eq!(pivoted.get_pixel(16, 40), &Rgba([255, 0, 0, 255]));
+    assert_eq!(pivoted.get_pixel(16, 24), &BACKGROUND);
+    assert_eq!(pivoted.get_pixel(32, 32), &BACKGROUND);
+}
+
+#[test]
+fn hit_testing_follows_the_transform() {
+    let mut interface = Interface::new(test_atlas());
+    interface.show(|ui| {
+        ui.add_element(Button::new([0.5, 0.5], RED, [0.8, 0.1], "solid").with_transform(Transform::new().with_rotation(90.0)), Some(1));
+    });
+
+    assert_eq!(interface.element_at([50.0, 20.0], [100, 100]).map(|element| element.get_id()), Some(1));
+    assert!(interface.element_at([20.0, 50.0], [100, 100]).is_none());
+}
+
+#[test]
+fn skewed_elements_are_hit_where_they_are_drawn() {
+    let button = Button::new([0.5, 0.5], RED, [0.2, 0.2], "solid").with_transform(Transform::new().with_skew([45.0, 0.0]));
+
+    // The bottom of the square leans right, by as far as it is below the center
+    assert!(button.is_cursor_within_bounds([65.0, 58.0], [50.0, 50.0], [20.0, 20.0]));
+    assert!(!button.is_cursor_within_bounds([45.0, 58.0], [50.0, 50.0], [20.0, 20.0]));
+}